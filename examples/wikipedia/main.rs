@@ -1,5 +1,5 @@
 use futures::StreamExt;
-use log::{info, warn};
+use log::info;
 use reqwest::Url;
 use std::fmt::Debug;
 use syphon::client::Client;
@@ -59,17 +59,20 @@ async fn main() {
     std::env::set_var("RUST_LOG", "syphon,wikipedia");
     env_logger::init();
 
+    // robots.txt is respected by default, and Wikipedia disallows
+    // `/wiki/Special:` pages, so start from an article.
     let wikipedia: Website<(), Output, _> = Website::handle(from_title)
         .start_with(
-            Url::parse("https://en.wikipedia.org/wiki/Special:Random")
+            Url::parse("https://en.wikipedia.org/wiki/Web_crawler")
                 .expect("Unable to parse starting Url"),
         )
         .parallel_limit(256)
+        .max_depth(3)
         .into();
 
     let mut stream = Client::handle(wikipedia).stream();
 
     while let Some(o) = stream.next().await {
-        info!("{:?}", o);
+        info!("{} ({} languages)", o.title, o.language);
     }
 }
@@ -0,0 +1,193 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::sync::watch;
+
+/// Why a website stopped crawling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    /// Every discovered url was visited.
+    Completed,
+    /// [`crate::control::CrawlHandle::drain`] was called.
+    Drained,
+    /// [`crate::control::CrawlHandle::stop`] was called.
+    Stopped,
+    MaxPages,
+    MaxOutputs,
+    MaxBytes,
+    MaxDuration,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Budget {
+    pub(crate) pages: Option<u64>,
+    pub(crate) outputs: Option<u64>,
+    pub(crate) bytes: Option<u64>,
+    pub(crate) duration: Option<Duration>,
+}
+
+/// Usage against one [`Budget`], shared by everything it applies to.
+pub(crate) struct Tracker {
+    budget: Budget,
+    deadline: Option<Instant>,
+    pages: AtomicU64,
+    outputs: AtomicU64,
+    bytes: AtomicU64,
+    exceeded: watch::Sender<Option<StopReason>>,
+}
+
+impl Tracker {
+    /// The duration limit counts from here.
+    pub(crate) fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            deadline: budget.duration.map(|duration| Instant::now() + duration),
+            pages: Default::default(),
+            outputs: Default::default(),
+            bytes: Default::default(),
+            exceeded: watch::Sender::new(None),
+        }
+    }
+
+    fn exceed(&self, reason: StopReason) {
+        self.exceeded.send_if_modified(|current| {
+            let first = current.is_none();
+            if first {
+                *current = Some(reason);
+            }
+            first
+        });
+    }
+
+    /// Counts `amount` against `limit`, returning whether the usage before
+    /// was still under it.
+    fn take(&self, used: &AtomicU64, limit: Option<u64>, amount: u64, reason: StopReason) -> bool {
+        let Some(limit) = limit else {
+            return true;
+        };
+        let before = used.fetch_add(amount, Ordering::Relaxed);
+        if before + amount >= limit {
+            self.exceed(reason);
+        }
+        before < limit
+    }
+
+    pub(crate) fn reason(&self) -> Option<StopReason> {
+        if self
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            self.exceed(StopReason::MaxDuration);
+        }
+        *self.exceeded.borrow()
+    }
+
+    /// Resolves once any limit is hit.
+    pub(crate) async fn exceeded(&self) -> StopReason {
+        let mut exceeded = self.exceeded.subscribe();
+        let deadline = async {
+            match self.deadline {
+                Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            Ok(reason) = exceeded.wait_for(Option::is_some) => reason.unwrap(),
+            _ = deadline => {
+                self.exceed(StopReason::MaxDuration);
+                StopReason::MaxDuration
+            }
+        }
+    }
+}
+
+/// The budgets of one website: its own, and the one of the whole crawl.
+pub(crate) struct Limits {
+    pub(crate) website: Tracker,
+    pub(crate) crawl: Arc<Tracker>,
+}
+
+impl Limits {
+    pub(crate) fn reason(&self) -> Option<StopReason> {
+        self.website.reason().or_else(|| self.crawl.reason())
+    }
+
+    pub(crate) async fn exceeded(&self) -> StopReason {
+        tokio::select! {
+            reason = self.website.exceeded() => reason,
+            reason = self.crawl.exceeded() => reason,
+        }
+    }
+
+    /// Reserves a request, returning false if a page budget is used up.
+    pub(crate) fn take_page(&self) -> bool {
+        [&self.website, &*self.crawl].iter().all(|tracker| {
+            tracker.take(
+                &tracker.pages,
+                tracker.budget.pages,
+                1,
+                StopReason::MaxPages,
+            )
+        })
+    }
+
+    /// Reserves an output, returning false if it must be dropped.
+    pub(crate) fn take_output(&self) -> bool {
+        [&self.website, &*self.crawl].iter().all(|tracker| {
+            tracker.take(
+                &tracker.outputs,
+                tracker.budget.outputs,
+                1,
+                StopReason::MaxOutputs,
+            )
+        })
+    }
+
+    pub(crate) fn add_bytes(&self, bytes: u64) {
+        for tracker in [&self.website, &*self.crawl] {
+            tracker.take(
+                &tracker.bytes,
+                tracker.budget.bytes,
+                bytes,
+                StopReason::MaxBytes,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let limits = Limits {
+            website: Tracker::new(Budget {
+                pages: Some(2),
+                ..Default::default()
+            }),
+            crawl: Arc::new(Tracker::new(Budget {
+                outputs: Some(1),
+                bytes: Some(100),
+                ..Default::default()
+            })),
+        };
+        assert!(limits.take_page());
+        assert_eq!(limits.reason(), None);
+        assert!(limits.take_page());
+        assert_eq!(limits.reason(), Some(StopReason::MaxPages));
+        assert!(!limits.take_page());
+
+        assert!(limits.take_output());
+        assert!(!limits.take_output());
+        assert_eq!(limits.crawl.reason(), Some(StopReason::MaxOutputs));
+
+        limits.add_bytes(1000);
+        assert_eq!(limits.crawl.reason(), Some(StopReason::MaxOutputs));
+    }
+}
@@ -1,15 +1,20 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, time::Duration};
 
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 
-use crate::website::{WebsitePair, WebsiteWrapper};
+use crate::{
+    budget::Budget,
+    control::CrawlHandle,
+    website::{WebsitePair, WebsiteWrapper},
+};
 
 pub struct Client<Out, Websites>
 where
     Websites: WebsiteWrapper<Out>,
 {
     websites: Websites,
+    budget: Budget,
     _marker: PhantomData<Out>,
 }
 
@@ -21,6 +26,7 @@ where
     pub fn handle(websites: Website) -> Self {
         Self {
             websites,
+            budget: Default::default(),
             _marker: Default::default(),
         }
     }
@@ -31,14 +37,47 @@ where
     ) -> Client<Out, WebsitePair<T, Website, Out>> {
         Client {
             websites: self.websites.pair(t),
+            budget: self.budget,
             _marker: Default::default(),
         }
     }
 
-    pub fn stream(mut self) -> ReceiverStream<Out> {
+    /// Stops every website once they have sent `pages` requests in total.
+    pub fn max_pages(mut self, pages: u64) -> Self {
+        self.budget.pages = Some(pages);
+        self
+    }
+
+    /// Stops every website once they have emitted `outputs` outputs in
+    /// total. Outputs past the limit are dropped.
+    pub fn max_outputs(mut self, outputs: u64) -> Self {
+        self.budget.outputs = Some(outputs);
+        self
+    }
+
+    /// Stops every website once they have downloaded `bytes` of response
+    /// bodies in total.
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.budget.bytes = Some(bytes);
+        self
+    }
+
+    /// Stops every website once `duration` has passed since the crawl
+    /// started.
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.budget.duration = Some(duration);
+        self
+    }
+
+    pub fn stream(self) -> ReceiverStream<Out> {
+        self.stream_with_handle().0
+    }
+
+    pub fn stream_with_handle(mut self) -> (ReceiverStream<Out>, CrawlHandle) {
         let (cx, rx) = mpsc::channel(16);
-        self.websites.init(cx);
+        let handle = CrawlHandle::new(self.budget);
+        self.websites.init(cx, &handle);
         self.websites.launch();
-        rx.into()
+        (rx.into(), handle)
     }
 }
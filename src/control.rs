@@ -0,0 +1,239 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::watch;
+
+use crate::budget::{Budget, Tracker};
+
+/// What every website of a crawl has been asked to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrawlState {
+    Running,
+    /// No new urls are dequeued; in-flight requests still complete.
+    Paused,
+    /// In-flight requests complete and their outputs are sent, then the
+    /// websites finish without dequeuing anything else.
+    Draining,
+    /// In-flight requests are aborted and the websites finish without
+    /// writing a final checkpoint.
+    Stopped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    Running,
+    Paused,
+    Finished,
+}
+
+struct Inner {
+    command: watch::Sender<CrawlState>,
+    phases: Mutex<Vec<watch::Receiver<Phase>>>,
+    budget: Arc<Tracker>,
+}
+
+/// Controls a crawl started by [`crate::client::Client::stream_with_handle`].
+///
+/// Dropping the handle leaves the crawl running.
+#[derive(Clone)]
+pub struct CrawlHandle {
+    inner: Arc<Inner>,
+}
+
+impl Default for CrawlHandle {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl CrawlHandle {
+    pub(crate) fn new(budget: Budget) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                command: watch::Sender::new(CrawlState::Running),
+                phases: Default::default(),
+                budget: Arc::new(Tracker::new(budget)),
+            }),
+        }
+    }
+
+    pub(crate) fn budget(&self) -> Arc<Tracker> {
+        self.inner.budget.clone()
+    }
+
+    pub(crate) fn register(&self) -> (watch::Receiver<CrawlState>, watch::Sender<Phase>) {
+        let (phase, rx) = watch::channel(Phase::Running);
+        self.inner.phases.lock().unwrap().push(rx);
+        (self.inner.command.subscribe(), phase)
+    }
+
+    pub fn state(&self) -> CrawlState {
+        *self.inner.command.borrow()
+    }
+
+    /// Stops dequeuing urls and resolves once every website has stopped.
+    pub async fn pause(&self) {
+        self.transition(|state| *state == CrawlState::Running, CrawlState::Paused);
+        self.wait_until(|phase| phase != Phase::Running).await
+    }
+
+    /// Resolves once every paused website is dequeuing again.
+    pub async fn resume(&self) {
+        self.transition(|state| *state == CrawlState::Paused, CrawlState::Running);
+        self.wait_until(|phase| phase != Phase::Paused).await
+    }
+
+    /// Finishes in-flight work, then resolves once every website is done and
+    /// the output stream has ended.
+    pub async fn drain(&self) {
+        self.transition(
+            |state| matches!(state, CrawlState::Running | CrawlState::Paused),
+            CrawlState::Draining,
+        );
+        self.wait_until(|phase| phase == Phase::Finished).await
+    }
+
+    /// Aborts in-flight work and resolves once every website is done. No
+    /// checkpoint is written, so a persisted crawl resumes from the last
+    /// periodic one.
+    pub async fn stop(&self) {
+        self.transition(|state| *state != CrawlState::Stopped, CrawlState::Stopped);
+        self.wait_until(|phase| phase == Phase::Finished).await
+    }
+
+    /// Resolves once every website has finished, for whatever reason.
+    pub async fn finished(&self) {
+        self.wait_until(|phase| phase == Phase::Finished).await
+    }
+
+    fn transition(&self, from: impl Fn(&CrawlState) -> bool, to: CrawlState) {
+        self.inner.command.send_if_modified(|state| {
+            let modify = from(state);
+            if modify {
+                *state = to;
+            }
+            modify
+        });
+    }
+
+    async fn wait_until(&self, done: impl Fn(Phase) -> bool) {
+        let phases = self.inner.phases.lock().unwrap().clone();
+        for mut phase in phases {
+            // A dropped sender means the website is gone, which counts as done.
+            let _ = phase.wait_for(|phase| done(*phase)).await;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "extractor"))]
+mod test {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        budget::StopReason,
+        client::Client,
+        test_server::{follow, Out, Reply, TestServer},
+        website::Website,
+    };
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[tokio::test]
+    async fn test_pause_resume() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links((1..=5).map(|n| format!("/{}", n))),
+            _ => Reply::html("leaf").delay(Duration::from_millis(100)),
+        })
+        .await;
+        let website = Website::handle(follow)
+            .start_with(server.url("/"))
+            .respect_robots(false)
+            .parallel_limit(1);
+        let (mut stream, handle) = Client::handle(Website::from(website)).stream_with_handle();
+        assert_eq!(stream.next().await, Some(Out("/".to_string())));
+
+        tokio::time::timeout(TIMEOUT, handle.pause()).await.unwrap();
+        assert_eq!(handle.state(), CrawlState::Paused);
+        // Let the request popped before the pause finish.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let fetched = server.requests().len();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(server.requests().len(), fetched);
+        assert!(fetched < 6);
+
+        tokio::time::timeout(TIMEOUT, handle.resume())
+            .await
+            .unwrap();
+        let rest = tokio::time::timeout(TIMEOUT, stream.collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert_eq!(rest.len(), 5);
+        assert_eq!(server.requests().len(), 6);
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_stop() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links(["/slow"]),
+            _ => Reply::html("leaf").delay(Duration::from_secs(30)),
+        })
+        .await;
+        let dir = std::env::temp_dir().join(format!("syphon-stop-{}", uuid::Uuid::new_v4()));
+        let website = Website::from(
+            Website::handle(follow)
+                .start_with(server.url("/"))
+                .respect_robots(false)
+                .persist(&dir),
+        );
+        let stats = website.stats();
+        let (mut stream, handle) = Client::handle(website).stream_with_handle();
+        assert_eq!(stream.next().await, Some(Out("/".to_string())));
+        while server.requests().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        tokio::time::timeout(TIMEOUT, handle.stop()).await.unwrap();
+        let rest = tokio::time::timeout(TIMEOUT, stream.collect::<Vec<_>>())
+            .await
+            .unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(stats.snapshot().stop_reason, Some(StopReason::Stopped));
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_drain() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links(["/slow"]),
+            "/slow" => Reply::links(["/never"]).delay(Duration::from_millis(300)),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        let website = Website::from(
+            Website::handle(follow)
+                .start_with(server.url("/"))
+                .respect_robots(false),
+        );
+        let stats = website.stats();
+        let (mut stream, handle) = Client::handle(website).stream_with_handle();
+        assert_eq!(stream.next().await, Some(Out("/".to_string())));
+        while server.requests().len() < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let drained = tokio::spawn(async move { handle.drain().await });
+        let rest = tokio::time::timeout(TIMEOUT, stream.collect::<Vec<_>>())
+            .await
+            .unwrap();
+        tokio::time::timeout(TIMEOUT, drained)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rest, [Out("/slow".to_string())]);
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(stats.snapshot().stop_reason, Some(StopReason::Drained));
+    }
+}
@@ -9,4 +9,11 @@ pub enum Error {
     JsonError(#[from] serde_json::Error),
     #[error("reqwest error")]
     ReqwestError(#[from] reqwest::Error),
+    #[cfg(feature = "serde")]
+    #[error("form encoding failed")]
+    FormError(#[from] serde_urlencoded::ser::Error),
+    #[error("io error")]
+    IoError(#[from] std::io::Error),
+    #[error("invalid crawl state: {0}")]
+    InvalidState(String),
 }
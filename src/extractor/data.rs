@@ -1,3 +1,5 @@
+use std::convert::Infallible;
+
 use async_trait::async_trait;
 
 use crate::response::{FromResponse, Response};
@@ -9,7 +11,9 @@ impl<InnerCtx, OuterCtx> FromResponse<OuterCtx> for Context<InnerCtx>
 where
     OuterCtx: Clone + Into<InnerCtx> + 'static + Sync,
 {
-    async fn from_response(_resp: &Response, data: &OuterCtx) -> Option<Self> {
-        Some(Self(data.clone().into()))
+    type Rejection = Infallible;
+
+    async fn from_response(_resp: &Response, data: &OuterCtx) -> Result<Self, Self::Rejection> {
+        Ok(Self(data.clone().into()))
     }
 }
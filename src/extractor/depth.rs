@@ -0,0 +1,17 @@
+use std::convert::Infallible;
+
+use async_trait::async_trait;
+
+use crate::response::{FromResponse, Response};
+
+/// How many links away from a starting url the current page is.
+pub struct Depth(pub usize);
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for Depth {
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.depth))
+    }
+}
@@ -0,0 +1,119 @@
+use std::{convert::Infallible, marker::PhantomData, time::SystemTime};
+
+use async_trait::async_trait;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use thiserror::Error;
+
+use crate::response::{FromResponse, Response};
+
+pub struct Headers(pub HeaderMap);
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for Headers {
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.headers.clone()))
+    }
+}
+
+/// A header that can be read into a typed value with [`Header`].
+pub trait TypedHeader: Sized {
+    fn name() -> HeaderName;
+
+    fn decode(value: &HeaderValue) -> Option<Self>;
+}
+
+/// The typed value of header `H`. The handler is skipped if the header is
+/// missing or malformed, unless it asks for an `Option<Header<H>>`.
+pub struct Header<H: TypedHeader>(pub H);
+
+#[derive(Debug, Error)]
+pub enum HeaderRejection {
+    #[error("header {0} is missing")]
+    Missing(HeaderName),
+    #[error("header {0} is malformed")]
+    Malformed(HeaderName),
+}
+
+#[async_trait]
+impl<H, Ctx> FromResponse<Ctx> for Header<H>
+where
+    H: TypedHeader,
+{
+    type Rejection = HeaderRejection;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        let value = resp
+            .headers
+            .get(H::name())
+            .ok_or_else(|| HeaderRejection::Missing(H::name()))?;
+        H::decode(value)
+            .map(Self)
+            .ok_or_else(|| HeaderRejection::Malformed(H::name()))
+    }
+}
+
+/// A header kept as its raw value, named by a type implementing
+/// [`HeaderNamed`]. Destructure it as `Header(Raw(value, _))`.
+pub struct Raw<N>(pub HeaderValue, pub PhantomData<N>);
+
+pub trait HeaderNamed {
+    const NAME: &'static str;
+}
+
+impl<N: HeaderNamed> TypedHeader for Raw<N> {
+    fn name() -> HeaderName {
+        HeaderName::from_static(N::NAME)
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        Some(Self(value.clone(), PhantomData))
+    }
+}
+
+macro_rules! impl_string_header {
+    ($ty:ident, $name:expr) => {
+        pub struct $ty(pub String);
+
+        impl TypedHeader for $ty {
+            fn name() -> HeaderName {
+                $name
+            }
+
+            fn decode(value: &HeaderValue) -> Option<Self> {
+                value.to_str().ok().map(|value| Self(value.to_string()))
+            }
+        }
+    };
+}
+
+impl_string_header!(ContentType, header::CONTENT_TYPE);
+impl_string_header!(ETag, header::ETAG);
+impl_string_header!(Location, header::LOCATION);
+
+pub struct ContentLength(pub u64);
+
+impl TypedHeader for ContentLength {
+    fn name() -> HeaderName {
+        header::CONTENT_LENGTH
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        value.to_str().ok()?.trim().parse().ok().map(Self)
+    }
+}
+
+pub struct LastModified(pub SystemTime);
+
+impl TypedHeader for LastModified {
+    fn name() -> HeaderName {
+        header::LAST_MODIFIED
+    }
+
+    fn decode(value: &HeaderValue) -> Option<Self> {
+        httpdate::parse_http_date(value.to_str().ok()?)
+            .ok()
+            .map(Self)
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    str::Utf8Error,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+
+use crate::response::{FromResponse, Response};
+
+/// The parsed page, shared with the `Selector` extractors of the response.
+/// Only lock it between awaits.
+pub struct Html(pub Arc<Mutex<scraper::Html>>);
+
+impl Html {
+    pub fn with<R>(&self, f: impl FnOnce(&scraper::Html) -> R) -> R {
+        f(&self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for Html {
+    type Rejection = Utf8Error;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        resp.dom().map(Self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        extractor::{SearchSelectors, Selector},
+        handler::{HandlerBox, HandlerWrapper},
+        next_action::NextAction,
+        test_server::Out,
+    };
+
+    #[derive(SearchSelectors)]
+    struct Title {
+        #[select(sel = "h1", text)]
+        title: String,
+    }
+
+    #[derive(SearchSelectors)]
+    struct Links {
+        #[select(sel = "a", attr = "href")]
+        links: Vec<String>,
+    }
+
+    #[tokio::test]
+    async fn test_dom_parsed_once() {
+        let resp = Arc::new(Response::test(
+            "https://example.com/",
+            r#"<h1>Title</h1><a href="/a"></a>"#,
+        ));
+        let seen = Arc::new(Mutex::new(None));
+        let handler = HandlerBox::from_handler({
+            let seen = seen.clone();
+            move |Selector(title): Selector<Title>,
+                  Html(dom): Html,
+                  Selector(links): Selector<Links>| {
+                *seen.lock().unwrap() = Some(dom);
+                async move {
+                    vec![NextAction::<(), Out>::PipeOutput(Out(format!(
+                        "{} {:?}",
+                        title.title, links.links
+                    )))]
+                }
+            }
+        });
+
+        let (actions, rejections) = handler.handle(resp.clone(), ()).await;
+        assert!(rejections.is_empty());
+        assert!(
+            matches!(&actions[..], [NextAction::PipeOutput(Out(out))] if out == r#"Title ["/a"]"#)
+        );
+        let dom = seen.lock().unwrap().take().unwrap();
+        assert!(Arc::ptr_eq(&dom, &resp.dom().unwrap()));
+
+        // Extractors read the document already parsed for the response
+        // rather than parsing the body again.
+        let resp = Response::test("https://example.com/", r#"<h1>Title</h1><a href="/a"></a>"#);
+        let cached = scraper::Html::parse_document(r#"<h1>Cached</h1><a href="/b"></a>"#);
+        let _ = resp.dom.set(Ok(Arc::new(Mutex::new(cached))));
+        let (actions, _) = handler.handle(Arc::new(resp), ()).await;
+        assert!(
+            matches!(&actions[..], [NextAction::PipeOutput(Out(out))] if out == r#"Cached ["/b"]"#)
+        );
+    }
+}
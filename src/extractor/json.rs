@@ -1,16 +1,23 @@
 use async_trait::async_trait;
 use serde::de::DeserializeOwned;
+use thiserror::Error;
 
 use crate::response::{FromResponse, Response};
 
 pub struct Json<T: DeserializeOwned>(pub T);
 
+#[derive(Debug, Error)]
+#[error("invalid json: {0}")]
+pub struct JsonRejection(#[from] pub serde_json::Error);
+
 #[async_trait]
 impl<T, Ctx> FromResponse<Ctx> for Json<T>
 where
     T: DeserializeOwned,
 {
-    async fn from_response(resp: &Response, _: &Ctx) -> Option<Self> {
-        resp.json().map(|j| Self(j)).ok()
+    type Rejection = JsonRejection;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(serde_json::from_slice(&resp.bytes)?))
     }
 }
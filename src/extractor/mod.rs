@@ -7,6 +7,20 @@ mod selector;
 pub use selector::*;
 
 mod data;
+mod depth;
+mod header;
+mod html;
+mod params;
+mod redirect;
+mod state;
+mod status;
 mod url;
 pub use data::*;
+pub use depth::*;
+pub use header::*;
+pub use html::*;
+pub use params::*;
+pub use redirect::*;
+pub use state::*;
+pub use status::*;
 pub use url::*;
@@ -0,0 +1,18 @@
+use std::{collections::HashMap, convert::Infallible};
+
+use async_trait::async_trait;
+
+use crate::response::{FromResponse, Response};
+
+/// The captures of the route that matched, empty for handlers that were not
+/// added with [`crate::website::WebsiteBuilder::route`].
+pub struct PathParams(pub HashMap<String, String>);
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for PathParams {
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.params.clone()))
+    }
+}
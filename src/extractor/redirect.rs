@@ -0,0 +1,18 @@
+use std::convert::Infallible;
+
+use async_trait::async_trait;
+
+use crate::response::{FromResponse, Redirect, Response};
+
+/// Every redirect followed to reach the page, in order. Empty if there
+/// were none.
+pub struct Redirects(pub Vec<Redirect>);
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for Redirects {
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.redirects.clone()))
+    }
+}
@@ -1,5 +1,8 @@
+use std::{any::type_name, str::Utf8Error};
+
 use crate::response::{FromResponse, Response};
 use async_trait::async_trait;
+use thiserror::Error;
 
 use scraper::Html;
 pub use syphon_macro::SearchSelectors;
@@ -9,14 +12,25 @@ pub trait SearchSelectors: Sized {
 
 pub struct Selector<T: SearchSelectors>(pub T);
 
+#[derive(Debug, Error)]
+pub enum SelectorRejection {
+    #[error("body is not utf-8: {0}")]
+    NotUtf8(#[from] Utf8Error),
+    #[error("{0} did not match the page")]
+    NoMatch(&'static str),
+}
+
 #[async_trait]
 impl<T, Ctx> FromResponse<Ctx> for Selector<T>
 where
     T: SearchSelectors,
 {
-    async fn from_response(resp: &Response, _: &Ctx) -> Option<Self> {
-        let dom = Html::parse_document(std::str::from_utf8(&resp.bytes).ok()?);
-        T::search(&dom).map(|x| Self(x))
+    type Rejection = SelectorRejection;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        resp.with_dom(T::search)?
+            .map(|x| Self(x))
+            .ok_or(SelectorRejection::NoMatch(type_name::<T>()))
     }
 }
 
@@ -32,15 +46,44 @@ mod test {
         anchor: String,
         #[select(sel = "#nothing", text)]
         nothing: Option<String>,
+        #[select(sel = "a[title='{x}']", attr = "href")]
+        braces: String,
     }
 
     #[test]
     fn test_search_selector_derive() {
-        let source = r#"<p id="text">Hello</p><a id="attr", href="/post"></a>"#;
+        let source = r#"<p id="text">Hello</p><a id="attr", href="/post"></a><a title="{x}" href="/braces"></a>"#;
         let dom = Html::parse_fragment(source);
         let target = Target::search(&dom).unwrap();
         assert_eq!(target.target, vec!["Hello"]);
         assert_eq!(target.anchor, "/post");
-        assert_eq!(target.nothing, None)
+        assert_eq!(target.nothing, None);
+        assert_eq!(target.braces, "/braces");
+    }
+
+    #[derive(SearchSelectors, Debug)]
+    struct Broken {
+        #[select(sel = "p[", text)]
+        broken: Option<String>,
+    }
+
+    #[derive(SearchSelectors, Debug)]
+    struct BrokenBraces {
+        #[select(sel = "p[{x}", text)]
+        broken: Option<String>,
+    }
+
+    #[test]
+    #[should_panic(expected = "on field `broken`")]
+    fn test_invalid_selector() {
+        let broken = Broken::search(&Html::parse_fragment("<p></p>"));
+        assert!(broken.unwrap().broken.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid selector \"p[{x}\" on field `broken`")]
+    fn test_invalid_selector_braces() {
+        let broken = BrokenBraces::search(&Html::parse_fragment("<p></p>"));
+        assert!(broken.unwrap().broken.is_none());
     }
 }
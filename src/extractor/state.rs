@@ -0,0 +1,69 @@
+use std::any::type_name;
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+use crate::response::{FromResponse, Response};
+
+/// A clone of the value given to
+/// [`crate::website::WebsiteBuilder::with_state`]. Handlers asking for a type
+/// that was never registered are skipped.
+pub struct State<T>(pub T);
+
+#[derive(Debug, Error)]
+#[error("no state of type {0} was registered")]
+pub struct MissingState(pub &'static str);
+
+#[async_trait]
+impl<T, Ctx> FromResponse<Ctx> for State<T>
+where
+    T: Clone + Send + Sync + 'static,
+{
+    type Rejection = MissingState;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        resp.state
+            .get::<T>()
+            .cloned()
+            .map(Self)
+            .ok_or(MissingState(type_name::<T>()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        handler::{HandlerBox, HandlerWrapper},
+        next_action::NextAction,
+        response::AppState,
+        test_server::Out,
+    };
+
+    #[tokio::test]
+    async fn test_state() {
+        let mut state = AppState::default();
+        state.insert(7u32);
+        let resp = Response {
+            state: Arc::new(state),
+            ..Response::test("https://example.com/", "")
+        };
+
+        let State(n) = State::<u32>::from_response(&resp, &()).await.unwrap();
+        assert_eq!(n, 7);
+        let Err(MissingState(name)) = State::<u64>::from_response(&resp, &()).await else {
+            panic!("expected a u64 state to be missing");
+        };
+        assert_eq!(name, "u64");
+
+        let handler = HandlerBox::from_handler(|State(n): State<u64>| async move {
+            vec![NextAction::<(), Out>::PipeOutput(Out(n.to_string()))]
+        });
+        let (actions, rejections) = handler.handle(Arc::new(resp), ()).await;
+        assert!(actions.is_empty());
+        assert_eq!(rejections.len(), 1);
+        assert!(rejections[0].extractor.contains("State<u64>"));
+    }
+}
@@ -0,0 +1,17 @@
+use std::convert::Infallible;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+
+use crate::response::{FromResponse, Response};
+
+pub struct Status(pub StatusCode);
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for Status {
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.status))
+    }
+}
@@ -1,3 +1,5 @@
+use std::convert::Infallible;
+
 use async_trait::async_trait;
 
 use crate::response::{FromResponse, Response};
@@ -6,7 +8,21 @@ pub struct Url(pub reqwest::Url);
 
 #[async_trait]
 impl<Ctx> FromResponse<Ctx> for Url {
-    async fn from_response(resp: &Response, _: &Ctx) -> Option<Self> {
-        Some(Self(resp.url.clone()))
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.url.clone()))
+    }
+}
+
+/// The url that was requested, before any redirect.
+pub struct OriginalUrl(pub reqwest::Url);
+
+#[async_trait]
+impl<Ctx> FromResponse<Ctx> for OriginalUrl {
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, _: &Ctx) -> Result<Self, Self::Rejection> {
+        Ok(Self(resp.original_url.clone()))
     }
 }
@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE},
+    Method, Url,
+};
+
+use crate::robots::wildcard_match;
+
+/// Rewrites urls so that trivially different spellings of the same page
+/// share one dedup key.
+///
+/// The host is lowercased and default ports are dropped by [`Url`] itself;
+/// on top of that the fragment is removed, query parameters are sorted, and
+/// parameters matching the strip-list are dropped.
+#[derive(Debug, Clone)]
+pub struct Canonicalize {
+    strip_params: Vec<String>,
+    trailing_slash: bool,
+}
+
+impl Default for Canonicalize {
+    fn default() -> Self {
+        Self {
+            strip_params: [
+                "utm_*",
+                "gclid",
+                "fbclid",
+                "phpsessid",
+                "jsessionid",
+                "sessionid",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            trailing_slash: true,
+        }
+    }
+}
+
+impl Canonicalize {
+    /// Adds query parameter names to drop, matched case-insensitively with
+    /// `*` as a wildcard.
+    pub fn strip_params<I, S>(mut self, params: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.strip_params
+            .extend(params.into_iter().map(|x| x.into().to_ascii_lowercase()));
+        self
+    }
+
+    /// Clears the strip-list, including the defaults.
+    pub fn keep_all_params(mut self) -> Self {
+        self.strip_params.clear();
+        self
+    }
+
+    /// Whether `/a/` and `/a` are the same page. On by default.
+    pub fn strip_trailing_slash(mut self, strip: bool) -> Self {
+        self.trailing_slash = strip;
+        self
+    }
+
+    fn is_stripped(&self, param: &str) -> bool {
+        let param = param.to_ascii_lowercase();
+        self.strip_params
+            .iter()
+            .any(|pattern| wildcard_match(pattern.as_bytes(), param.as_bytes(), true))
+    }
+
+    pub fn apply(&self, url: &Url) -> Url {
+        let mut url = url.clone();
+        url.set_fragment(None);
+
+        if url.query().is_some() {
+            let mut pairs: Vec<(String, String)> = url
+                .query_pairs()
+                .filter(|(key, _)| !self.is_stripped(key))
+                .map(|(key, value)| (key.into_owned(), value.into_owned()))
+                .collect();
+            pairs.sort();
+            if pairs.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+        }
+
+        if self.trailing_slash && url.path().len() > 1 && url.path().ends_with('/') {
+            let path = url.path().trim_end_matches('/').to_string();
+            url.set_path(if path.is_empty() { "/" } else { &path });
+        }
+        url
+    }
+}
+
+/// Identifies a request for deduplication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub u128);
+
+pub type FingerprintFn =
+    Arc<dyn Fn(&Method, &Url, &HeaderMap, Option<&[u8]>) -> Fingerprint + Send + Sync>;
+
+/// A 128-bit FNV-1a hash, stable across runs and platforms.
+#[derive(Clone, Copy)]
+pub struct FingerprintHasher(u128);
+
+impl Default for FingerprintHasher {
+    fn default() -> Self {
+        Self(0x6c62272e07bb014262b821756295c58d)
+    }
+}
+
+impl FingerprintHasher {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u128;
+            self.0 = self.0.wrapping_mul(0x0000000001000000000000000000013b);
+        }
+    }
+
+    /// Writes a length-prefixed field so adjacent fields cannot run together.
+    pub fn write_field(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    pub fn finish(&self) -> Fingerprint {
+        Fingerprint(self.0)
+    }
+}
+
+/// Headers that select a different representation of the same url. Others,
+/// such as `referer`, `cookie` or `authorization`, often change from one
+/// request to the next and are left out of [`default_fingerprint`].
+pub const FINGERPRINT_HEADERS: [HeaderName; 3] = [ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE];
+
+/// Hashes the method, the canonical url, the [`FINGERPRINT_HEADERS`] set on
+/// the request, and the body. The timeout is left out, as it does not change
+/// what is fetched.
+pub fn default_fingerprint(
+    method: &Method,
+    url: &Url,
+    headers: &HeaderMap,
+    body: Option<&[u8]>,
+) -> Fingerprint {
+    let mut hasher = FingerprintHasher::default();
+    hasher.write_field(method.as_str().as_bytes());
+    hasher.write_field(url.as_str().as_bytes());
+    for name in &FINGERPRINT_HEADERS {
+        let values = headers.get_all(name);
+        hasher.write(&(values.iter().count() as u64).to_le_bytes());
+        for value in values {
+            hasher.write_field(value.as_bytes());
+        }
+    }
+    hasher.write_field(body.unwrap_or_default());
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn canonical(s: &str) -> String {
+        Canonicalize::default()
+            .apply(&Url::parse(s).unwrap())
+            .to_string()
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        assert_eq!(
+            canonical("HTTP://Example.COM:80/a/?b=2&a=1&utm_source=x#top"),
+            "http://example.com/a?a=1&b=2"
+        );
+        assert_eq!(
+            canonical("https://example.com/?utm_medium=y"),
+            "https://example.com/"
+        );
+        assert_ne!(
+            canonical("https://example.com/item?id=1"),
+            canonical("https://example.com/item?id=2")
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        let url = Url::parse("https://example.com/search").unwrap();
+        let none = HeaderMap::new();
+        let get = default_fingerprint(&Method::GET, &url, &none, None);
+        assert_eq!(get, default_fingerprint(&Method::GET, &url, &none, None));
+        assert_ne!(get, default_fingerprint(&Method::POST, &url, &none, None));
+        assert_ne!(
+            default_fingerprint(&Method::POST, &url, &none, Some(b"q=1")),
+            default_fingerprint(&Method::POST, &url, &none, Some(b"q=2"))
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_headers() {
+        let url = Url::parse("https://example.com/api").unwrap();
+        let headers = |pairs: &[(&'static str, &'static str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        reqwest::header::HeaderName::from_static(name),
+                        reqwest::header::HeaderValue::from_static(value),
+                    )
+                })
+                .collect::<HeaderMap>()
+        };
+        let fingerprint =
+            |headers: &HeaderMap| default_fingerprint(&Method::GET, &url, headers, None);
+        let json = fingerprint(&headers(&[("accept", "application/json")]));
+        assert_ne!(json, fingerprint(&HeaderMap::new()));
+        assert_ne!(json, fingerprint(&headers(&[("accept", "text/html")])));
+        assert_eq!(
+            fingerprint(&headers(&[
+                ("accept", "text/html"),
+                ("referer", "https://a.com/")
+            ])),
+            fingerprint(&headers(&[
+                ("accept", "text/html"),
+                ("referer", "https://b.com/")
+            ]))
+        );
+        assert_eq!(
+            fingerprint(&headers(&[
+                ("cookie", "a=1"),
+                ("authorization", "Bearer a")
+            ])),
+            fingerprint(&HeaderMap::new())
+        );
+    }
+}
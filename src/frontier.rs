@@ -0,0 +1,704 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hashbrown::HashMap;
+use log::error;
+use reqwest::Url;
+use tokio::sync::Notify;
+
+use crate::{
+    next_action::NextUrl,
+    order::{CrawlOrder, Rank},
+    spill::Spill,
+};
+
+pub(crate) fn host_key(url: &Url) -> String {
+    url.host_str().unwrap_or_default().to_string()
+}
+
+struct Queued<Ctx> {
+    rank: Rank,
+    next: NextUrl<Ctx>,
+}
+
+impl<Ctx> PartialEq for Queued<Ctx> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
+    }
+}
+
+impl<Ctx> Eq for Queued<Ctx> {}
+
+impl<Ctx> PartialOrd for Queued<Ctx> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Ctx> Ord for Queued<Ctx> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Whether a host's `robots.txt` is known, when the frontier waits for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RobotsState {
+    /// Nothing was popped for the host yet.
+    Unknown,
+    /// The first popped url is loading it; nothing else is popped meanwhile.
+    Loading,
+    Known,
+}
+
+struct HostQueue<Ctx> {
+    pending: BinaryHeap<Queued<Ctx>>,
+    in_flight: usize,
+    ready_at: Instant,
+    min_delay: Duration,
+    delay: Duration,
+    limit: usize,
+    robots: RobotsState,
+}
+
+impl<Ctx> HostQueue<Ctx> {
+    fn new((delay, limit): (Duration, usize), robots: RobotsState) -> Self {
+        Self {
+            pending: Default::default(),
+            in_flight: 0,
+            ready_at: Instant::now(),
+            min_delay: Duration::ZERO,
+            delay,
+            limit,
+            robots,
+        }
+    }
+}
+
+struct Inner<Ctx> {
+    hosts: HashMap<String, HostQueue<Ctx>>,
+    rotation: VecDeque<String>,
+    /// Urls in the host queues, not counting spilled ones.
+    queued: usize,
+    spill: Option<Spill<Ctx>>,
+    seq: u64,
+    /// Popped and delayed urls by id, kept only when tracking so that a
+    /// snapshot can include them.
+    parked: Option<HashMap<u64, NextUrl<Ctx>>>,
+    next_id: u64,
+    /// Urls that are queued, waiting to be queued, or popped but not yet
+    /// finished. The crawl is over once this drops to zero.
+    outstanding: usize,
+}
+
+enum TryPop<Ctx> {
+    Ready(NextUrl<Ctx>, HostSlot<Ctx>, Work<Ctx>),
+    Wait(Option<Instant>),
+    Done,
+}
+
+/// Per-host priority queues. Each pop takes the highest ranked url among the
+/// hosts that are ready, round-robin between equally ranked hosts, honoring a
+/// minimum delay between requests and a cap on in-flight requests per host.
+///
+/// `host_delay` and `host_limit` are hard bounds; per-host values set through
+/// [`Frontier::set_host_limits`] can only make a host slower.
+pub(crate) struct Frontier<Ctx> {
+    inner: Mutex<Inner<Ctx>>,
+    notify: Notify,
+    host_delay: Duration,
+    host_limit: usize,
+    host_defaults: (Duration, usize),
+    order: CrawlOrder,
+    wait_for_robots: bool,
+}
+
+/// Marks a request as in flight for its host until dropped.
+pub(crate) struct HostSlot<Ctx> {
+    frontier: Arc<Frontier<Ctx>>,
+    host: String,
+    /// The host's `ready_at` before and after the pop.
+    turn: (Instant, Instant),
+}
+
+impl<Ctx> HostSlot<Ctx> {
+    /// Gives the host its turn back, for a url dropped without being sent.
+    pub(crate) fn skip(self) {
+        let mut inner = self.frontier.inner.lock().unwrap();
+        if let Some(queue) = inner.hosts.get_mut(&self.host) {
+            let (before, after) = self.turn;
+            if queue.ready_at == after {
+                queue.ready_at = before;
+            }
+        }
+    }
+}
+
+/// Keeps the crawl alive until a popped url and everything it leads to has
+/// been pushed back into the frontier.
+pub(crate) struct Work<Ctx> {
+    frontier: Arc<Frontier<Ctx>>,
+    id: Option<u64>,
+}
+
+impl<Ctx> Drop for Work<Ctx> {
+    fn drop(&mut self) {
+        let mut inner = self.frontier.inner.lock().unwrap();
+        inner.outstanding -= 1;
+        inner.unpark(self.id);
+        drop(inner);
+        self.frontier.notify.notify_one();
+    }
+}
+
+impl<Ctx: Clone> Inner<Ctx> {
+    fn park(&mut self, next: &NextUrl<Ctx>) -> Option<u64> {
+        let parked = self.parked.as_mut()?;
+        self.next_id += 1;
+        parked.insert(self.next_id, next.clone());
+        Some(self.next_id)
+    }
+}
+
+impl<Ctx> Inner<Ctx> {
+    fn unpark(&mut self, id: Option<u64>) {
+        if let (Some(parked), Some(id)) = (self.parked.as_mut(), id) {
+            parked.remove(&id);
+        }
+    }
+}
+
+impl<Ctx> Drop for HostSlot<Ctx> {
+    fn drop(&mut self) {
+        let mut inner = self.frontier.inner.lock().unwrap();
+        if let Some(queue) = inner.hosts.get_mut(&self.host) {
+            queue.in_flight -= 1;
+        }
+        drop(inner);
+        self.frontier.notify.notify_one();
+    }
+}
+
+impl<Ctx> Frontier<Ctx> {
+    pub(crate) fn new(host_delay: Duration, host_limit: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                hosts: Default::default(),
+                rotation: Default::default(),
+                queued: 0,
+                spill: None,
+                seq: 0,
+                parked: None,
+                next_id: 0,
+                outstanding: 0,
+            }),
+            notify: Notify::new(),
+            host_delay,
+            host_limit: host_limit.max(1),
+            host_defaults: (Duration::ZERO, usize::MAX),
+            order: Default::default(),
+            wait_for_robots: false,
+        }
+    }
+
+    pub(crate) fn with_order(mut self, order: CrawlOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Pops a single url for each new host until [`Frontier::robots_loaded`]
+    /// is called for it, so that its `Crawl-delay` applies from the start.
+    pub(crate) fn with_robots(mut self) -> Self {
+        self.wait_for_robots = true;
+        self
+    }
+
+    /// Bounds the host queues, moving the lowest ranked urls to disk.
+    pub(crate) fn with_spill(self, spill: Spill<Ctx>) -> Self {
+        self.inner.lock().unwrap().spill = Some(spill);
+        self
+    }
+
+    /// Keeps a copy of popped and delayed urls for [`Frontier::snapshot`].
+    pub(crate) fn with_tracking(self) -> Self {
+        self.inner.lock().unwrap().parked = Some(Default::default());
+        self
+    }
+
+    /// Every url that has not been finished yet: queued, delayed, or popped
+    /// but still being worked on. Spilled urls are read from disk as the
+    /// iterator advances, after the lock has been released.
+    pub(crate) fn snapshot(&self) -> impl Iterator<Item = NextUrl<Ctx>>
+    where
+        Ctx: Clone,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        let spilled = match inner.spill.as_mut().map(Spill::snapshot) {
+            Some(Ok(spilled)) => Some(spilled),
+            Some(Err(err)) => {
+                error!("unable to read spilled urls: {}", err);
+                None
+            }
+            None => None,
+        };
+        let queued: Vec<_> = inner
+            .hosts
+            .values()
+            .flat_map(|queue| queue.pending.iter().map(|queued| queued.next.clone()))
+            .chain(
+                inner
+                    .parked
+                    .iter()
+                    .flat_map(|parked| parked.values().cloned()),
+            )
+            .collect();
+        let spilled = spilled.into_iter().flatten().filter_map(|next| {
+            next.map_err(|err| error!("unable to read spilled urls: {}", err))
+                .ok()
+        });
+        queued.into_iter().chain(spilled)
+    }
+
+    /// Per-host delay and limit given to hosts seen for the first time.
+    pub(crate) fn with_host_defaults(mut self, delay: Duration, limit: usize) -> Self {
+        self.host_defaults = (delay, limit);
+        self
+    }
+
+    fn host_queue<'a>(&self, inner: &'a mut Inner<Ctx>, host: String) -> &'a mut HostQueue<Ctx> {
+        inner.hosts.entry(host).or_insert_with(|| {
+            let robots = match self.wait_for_robots {
+                true => RobotsState::Unknown,
+                false => RobotsState::Known,
+            };
+            HostQueue::new(self.host_defaults, robots)
+        })
+    }
+
+    pub(crate) fn push(&self, next: NextUrl<Ctx>) {
+        self.inner.lock().unwrap().outstanding += 1;
+        self.insert(next);
+    }
+
+    /// Pushes `next` once `delay` has passed, without letting the crawl
+    /// finish in the meantime.
+    pub(crate) fn push_after(self: &Arc<Self>, next: NextUrl<Ctx>, delay: Duration)
+    where
+        Ctx: Clone + Send + 'static,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        inner.outstanding += 1;
+        let id = inner.park(&next);
+        drop(inner);
+        let frontier = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            frontier.inner.lock().unwrap().unpark(id);
+            frontier.insert(next);
+        });
+    }
+
+    fn insert(&self, next: NextUrl<Ctx>) {
+        let host = host_key(&next.url);
+        let mut inner = self.inner.lock().unwrap();
+        inner.seq += 1;
+        let rank = self.order.rank(&next, inner.seq);
+        let queued = inner.queued;
+        if let Some(spill) = inner.spill.as_mut().filter(|spill| queued >= spill.window) {
+            match spill.push(rank, &next) {
+                Ok(()) => return,
+                Err(err) => error!(
+                    "unable to spill {}, keeping it in memory: {}",
+                    next.url, err
+                ),
+            }
+        }
+        self.enqueue(&mut inner, host, rank, next);
+        drop(inner);
+        self.notify.notify_one();
+    }
+
+    fn enqueue(&self, inner: &mut Inner<Ctx>, host: String, rank: Rank, next: NextUrl<Ctx>) {
+        let queue = self.host_queue(inner, host.clone());
+        let was_idle = queue.pending.is_empty();
+        queue.pending.push(Queued { rank, next });
+        if was_idle {
+            inner.rotation.push_back(host);
+        }
+        inner.queued += 1;
+    }
+
+    /// Whether `queue` can take a request at `now`.
+    fn is_ready(&self, queue: &HostQueue<Ctx>, now: Instant) -> bool {
+        queue.robots != RobotsState::Loading
+            && queue.in_flight < self.host_limit.min(queue.limit).max(1)
+            && queue.ready_at <= now
+    }
+
+    /// Moves spilled urls back into the host queues: any that outrank
+    /// everything in memory, and enough to fill the window once it is half
+    /// empty, so disk is read in batches.
+    ///
+    /// Urls of hosts that cannot take a request right now do not count
+    /// against the window, so that a paused or throttled host cannot keep
+    /// the others on disk. Memory stays under twice the window.
+    fn refill(&self, inner: &mut Inner<Ctx>) {
+        let Some(mut spill) = inner.spill.take() else {
+            return;
+        };
+        let now = Instant::now();
+        let mut best = inner
+            .rotation
+            .iter()
+            .map(|host| inner.hosts[host].pending.peek().unwrap().rank.level())
+            .max();
+        let ready: usize = inner
+            .rotation
+            .iter()
+            .map(|host| &inner.hosts[host])
+            .filter(|queue| self.is_ready(queue, now))
+            .map(|queue| queue.pending.len())
+            .sum();
+        let refilling = ready <= spill.window / 2;
+        let mut loaded = 0;
+        while let Some(rank) = spill.peek() {
+            let outranks = best.is_none_or(|best| rank.level() > best);
+            let fill =
+                refilling && ready + loaded < spill.window && inner.queued < 2 * spill.window;
+            if !(outranks || fill) {
+                break;
+            }
+            match spill.pop() {
+                Ok(Some((rank, next))) => {
+                    best = best.max(Some(rank.level()));
+                    loaded += 1;
+                    self.enqueue(inner, host_key(&next.url), rank, next);
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    error!("unable to read spilled url, dropping it: {}", err);
+                    inner.outstanding -= 1;
+                }
+            }
+        }
+        inner.spill = Some(spill);
+    }
+
+    /// Holds back every request to `url`'s host until `delay` has passed.
+    pub(crate) fn pause_host(&self, url: &Url, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut inner = self.inner.lock().unwrap();
+        let queue = self.host_queue(&mut inner, host_key(url));
+        queue.ready_at = queue.ready_at.max(until);
+    }
+
+    /// Lets `host` take more requests, no more often than `crawl_delay`
+    /// counting from now, when its first request goes out. Only the first
+    /// call for a host has any effect.
+    pub(crate) fn robots_loaded(&self, host: &str, crawl_delay: Option<Duration>) {
+        let mut inner = self.inner.lock().unwrap();
+        let queue = self.host_queue(&mut inner, host.to_string());
+        if queue.robots == RobotsState::Known {
+            return;
+        }
+        queue.robots = RobotsState::Known;
+        if let Some(delay) = crawl_delay {
+            queue.min_delay = delay;
+            queue.ready_at = queue.ready_at.max(Instant::now() + delay);
+        }
+        drop(inner);
+        self.notify.notify_one();
+    }
+
+    pub(crate) fn set_host_limits(&self, host: &str, delay: Duration, limit: usize) {
+        let mut inner = self.inner.lock().unwrap();
+        let queue = self.host_queue(&mut inner, host.to_string());
+        queue.delay = delay;
+        queue.limit = limit;
+        drop(inner);
+        self.notify.notify_one();
+    }
+
+    /// Waits for the next url whose host is ready to take another request.
+    /// Returns `None` once nothing is queued and no popped work is pending.
+    pub(crate) async fn pop(self: &Arc<Self>) -> Option<(NextUrl<Ctx>, HostSlot<Ctx>, Work<Ctx>)>
+    where
+        Ctx: Clone,
+    {
+        loop {
+            match self.try_pop() {
+                TryPop::Ready(next, slot, work) => return Some((next, slot, work)),
+                TryPop::Done => return None,
+                TryPop::Wait(Some(at)) => {
+                    tokio::select! {
+                        _ = self.notify.notified() => {}
+                        _ = tokio::time::sleep_until(at.into()) => {}
+                    }
+                }
+                TryPop::Wait(None) => self.notify.notified().await,
+            }
+        }
+    }
+
+    fn try_pop(self: &Arc<Self>) -> TryPop<Ctx>
+    where
+        Ctx: Clone,
+    {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.outstanding == 0 {
+            return TryPop::Done;
+        }
+        self.refill(&mut inner);
+        // After the refill, which may have added hosts ready from now on.
+        let now = Instant::now();
+        let Inner {
+            hosts,
+            rotation,
+            queued,
+            ..
+        } = &mut *inner;
+        let mut wake_at: Option<Instant> = None;
+        let mut best: Option<(usize, (i64, i64))> = None;
+        for (idx, host) in rotation.iter().enumerate() {
+            let queue = &hosts[host];
+            if queue.robots == RobotsState::Loading
+                || queue.in_flight >= self.host_limit.min(queue.limit).max(1)
+            {
+                continue;
+            }
+            if queue.ready_at > now {
+                wake_at = Some(wake_at.map_or(queue.ready_at, |x| x.min(queue.ready_at)));
+                continue;
+            }
+            let level = queue.pending.peek().unwrap().rank.level();
+            if best.is_none_or(|(_, best)| level > best) {
+                best = Some((idx, level));
+            }
+        }
+        let Some((idx, _)) = best else {
+            return TryPop::Wait(wake_at);
+        };
+
+        let host = rotation.remove(idx).unwrap();
+        let queue = hosts.get_mut(&host).unwrap();
+        let next = queue.pending.pop().unwrap().next;
+        queue.in_flight += 1;
+        *queued -= 1;
+        if queue.robots == RobotsState::Unknown {
+            queue.robots = RobotsState::Loading;
+        }
+        let before = queue.ready_at;
+        queue.ready_at = now + self.host_delay.max(queue.min_delay).max(queue.delay);
+        let turn = (before, queue.ready_at);
+        if !queue.pending.is_empty() {
+            rotation.push_back(host.clone());
+        }
+        let id = inner.park(&next);
+        let slot = HostSlot {
+            frontier: self.clone(),
+            host,
+            turn,
+        };
+        let work = Work {
+            frontier: self.clone(),
+            id,
+        };
+        TryPop::Ready(next, slot, work)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn next(path: &str, depth: usize) -> NextUrl<()> {
+        NextUrl {
+            depth,
+            ..NextUrl::new(
+                Url::parse("https://example.com")
+                    .unwrap()
+                    .join(path)
+                    .unwrap(),
+                (),
+            )
+        }
+    }
+
+    fn drain(frontier: Frontier<()>, urls: Vec<NextUrl<()>>) -> Vec<String> {
+        let frontier = Arc::new(frontier);
+        for url in urls {
+            frontier.push(url);
+        }
+        let mut order = Vec::new();
+        while let TryPop::Ready(next, _, _) = frontier.try_pop() {
+            order.push(next.url.path().to_string());
+        }
+        order
+    }
+
+    #[test]
+    fn test_order() {
+        let urls = || {
+            vec![
+                next("/a", 1),
+                next("/b", 2),
+                next("/c", 1),
+                next("/d", 2).priority(1),
+            ]
+        };
+        let frontier = || Frontier::new(Duration::ZERO, usize::MAX);
+        assert_eq!(drain(frontier(), urls()), ["/d", "/a", "/c", "/b"]);
+        assert_eq!(
+            drain(frontier().with_order(CrawlOrder::DepthFirst), urls()),
+            ["/d", "/b", "/c", "/a"]
+        );
+        let order = CrawlOrder::custom(|url, _| (url.path() == "/c") as i64);
+        assert_eq!(
+            drain(frontier().with_order(order), urls()),
+            ["/d", "/c", "/a", "/b"]
+        );
+    }
+
+    fn popped_host(pop: TryPop<()>) -> (String, HostSlot<()>, Work<()>) {
+        match pop {
+            TryPop::Ready(next, slot, work) => (host_key(&next.url), slot, work),
+            _ => panic!("expected a url to be ready"),
+        }
+    }
+
+    #[test]
+    fn test_host_delay() {
+        let frontier = Arc::new(Frontier::new(Duration::from_secs(60), usize::MAX));
+        let other = Url::parse("https://other.com/").unwrap();
+        frontier.push(next("/a", 0));
+        frontier.push(next("/b", 0));
+        frontier.push(NextUrl::new(other, ()));
+
+        let before = Instant::now();
+        let (host, _, _) = popped_host(frontier.try_pop());
+        assert_eq!(host, "example.com");
+        let (host, _, _) = popped_host(frontier.try_pop());
+        assert_eq!(host, "other.com");
+        match frontier.try_pop() {
+            TryPop::Wait(Some(at)) => assert!(at >= before + Duration::from_secs(60)),
+            _ => panic!("expected example.com to wait for its delay"),
+        }
+    }
+
+    #[test]
+    fn test_host_limit() {
+        let frontier = Arc::new(Frontier::new(Duration::ZERO, 1));
+        let other = Url::parse("https://other.com/").unwrap();
+        frontier.push(next("/a", 0));
+        frontier.push(next("/b", 0));
+        frontier.push(NextUrl::new(other, ()));
+
+        let (host, slot, _a) = popped_host(frontier.try_pop());
+        assert_eq!(host, "example.com");
+        let (host, _, _) = popped_host(frontier.try_pop());
+        assert_eq!(host, "other.com");
+        assert!(matches!(frontier.try_pop(), TryPop::Wait(None)));
+        drop(slot);
+        let (host, _, _) = popped_host(frontier.try_pop());
+        assert_eq!(host, "example.com");
+    }
+
+    #[test]
+    fn test_wait_for_robots() {
+        let frontier = Arc::new(Frontier::new(Duration::ZERO, usize::MAX).with_robots());
+        frontier.push(next("/a", 0));
+        frontier.push(next("/b", 0));
+        assert!(matches!(frontier.try_pop(), TryPop::Ready(..)));
+        assert!(matches!(frontier.try_pop(), TryPop::Wait(None)));
+
+        let before = Instant::now();
+        frontier.robots_loaded("example.com", Some(Duration::from_secs(5)));
+        match frontier.try_pop() {
+            TryPop::Wait(Some(at)) => assert!(at >= before + Duration::from_secs(5)),
+            _ => panic!("expected the host to wait for its crawl delay"),
+        }
+    }
+
+    #[test]
+    fn test_skip() {
+        let frontier = Arc::new(Frontier::new(Duration::from_secs(60), usize::MAX));
+        frontier.push(next("/a", 0));
+        frontier.push(next("/b", 0));
+        frontier.push(next("/c", 0));
+        let TryPop::Ready(_, slot, _) = frontier.try_pop() else {
+            panic!("expected /a to be ready");
+        };
+        slot.skip();
+        let TryPop::Ready(_, slot, _) = frontier.try_pop() else {
+            panic!("expected a skipped url to give its turn back");
+        };
+        drop(slot);
+        assert!(matches!(frontier.try_pop(), TryPop::Wait(Some(_))));
+    }
+
+    #[test]
+    fn test_spill() {
+        let urls: Vec<_> = (0..20).map(|i| next(&format!("/{}", i), i % 3)).collect();
+        let expected = drain(Frontier::new(Duration::ZERO, usize::MAX), urls.clone());
+
+        let dir = std::env::temp_dir().join(format!("syphon-spill-{}", uuid::Uuid::new_v4()));
+        let spill = Spill::new(&dir, 4, crate::state::Codec::new()).unwrap();
+        let frontier = Frontier::new(Duration::ZERO, usize::MAX).with_spill(spill);
+        assert_eq!(drain(frontier, urls), expected);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn test_spill_paused_host() {
+        let dir = std::env::temp_dir().join(format!("syphon-spill-{}", uuid::Uuid::new_v4()));
+        let spill = Spill::new(&dir, 4, crate::state::Codec::new()).unwrap();
+        let frontier = Arc::new(Frontier::new(Duration::ZERO, usize::MAX).with_spill(spill));
+        for i in 0..4 {
+            frontier.push(next(&format!("/{}", i), 0));
+        }
+        for i in 0..4 {
+            let url = Url::parse(&format!("https://other.com/{}", i)).unwrap();
+            frontier.push(NextUrl::new(url, ()));
+        }
+        frontier.pause_host(
+            &Url::parse("https://example.com/").unwrap(),
+            Duration::from_secs(60),
+        );
+
+        for _ in 0..4 {
+            let (host, slot, work) = popped_host(frontier.try_pop());
+            assert_eq!(host, "other.com");
+            frontier.robots_loaded(&host, None);
+            drop((slot, work));
+        }
+        assert!(matches!(frontier.try_pop(), TryPop::Wait(Some(_))));
+        drop(frontier);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_spill_snapshot() {
+        let dir = std::env::temp_dir().join(format!("syphon-spill-{}", uuid::Uuid::new_v4()));
+        let spill = Spill::new(&dir, 4, crate::state::Codec::new()).unwrap();
+        let frontier = Arc::new(Frontier::new(Duration::ZERO, usize::MAX).with_spill(spill));
+        for i in 0..20 {
+            frontier.push(next(&format!("/{}", i), i));
+        }
+        let snapshot = frontier.snapshot();
+        // Reading every spilled url back must not truncate the segment under
+        // the snapshot.
+        while let TryPop::Ready(..) = frontier.try_pop() {}
+        let mut paths: Vec<_> = snapshot.map(|next| next.url.path().to_string()).collect();
+        paths.sort();
+        let mut expected: Vec<_> = (0..20).map(|i| format!("/{}", i)).collect();
+        expected.sort();
+        assert_eq!(paths, expected);
+        drop(frontier);
+        std::fs::remove_dir(dir).unwrap();
+    }
+}
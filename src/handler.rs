@@ -1,16 +1,150 @@
-use std::{marker::PhantomData, pin::Pin, sync::Arc};
+use std::{borrow::Cow, marker::PhantomData, ops::RangeInclusive, pin::Pin, sync::Arc};
 
 use async_trait::async_trait;
 use futures::{
     future::{join_all, BoxFuture},
     Future,
 };
+use reqwest::{StatusCode, Url};
 
 use crate::{
     next_action::{IntoNextActionVec, NextActionVector, WebsiteOutput},
-    response::{FromResponse, Response},
+    response::{FromResponse, Rejection, Response},
+    retry::Failure,
 };
 
+/// What went wrong with a request, given to error handlers.
+#[derive(Clone)]
+pub enum FetchError {
+    /// The server answered with a status outside 2xx.
+    Status(Arc<Response>),
+    /// No response came back, even after retries.
+    Failure(Url, Failure),
+}
+
+impl FetchError {
+    pub fn url(&self) -> &Url {
+        match self {
+            Self::Status(resp) => &resp.original_url,
+            Self::Failure(url, _) => url,
+        }
+    }
+}
+
+/// A set of status codes a handler accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusFilter(Vec<RangeInclusive<u16>>);
+
+impl StatusFilter {
+    pub fn success() -> Self {
+        Self::range(200..=299)
+    }
+
+    pub fn any() -> Self {
+        Self::range(100..=999)
+    }
+
+    /// Everything outside 2xx.
+    pub fn errors() -> Self {
+        Self(vec![100..=199, 300..=999])
+    }
+
+    pub fn range(range: RangeInclusive<u16>) -> Self {
+        Self(vec![range])
+    }
+
+    pub fn codes(codes: impl IntoIterator<Item = StatusCode>) -> Self {
+        Self(
+            codes
+                .into_iter()
+                .map(|code| code.as_u16()..=code.as_u16())
+                .collect(),
+        )
+    }
+
+    pub fn or(mut self, other: Self) -> Self {
+        self.0.extend(other.0);
+        self
+    }
+
+    pub fn accepts(&self, status: StatusCode) -> bool {
+        self.0.iter().any(|range| range.contains(&status.as_u16()))
+    }
+}
+
+impl Default for StatusFilter {
+    fn default() -> Self {
+        Self::success()
+    }
+}
+
+/// A handler that only runs for responses whose status is in `statuses`,
+/// instead of only 2xx.
+pub fn on_status<H>(statuses: StatusFilter, handler: H) -> OnStatus<H> {
+    OnStatus { statuses, handler }
+}
+
+#[derive(Clone)]
+pub struct OnStatus<H> {
+    statuses: StatusFilter,
+    handler: H,
+}
+
+impl<H, T, Ctx, Out> Handler<T, Ctx, Out> for OnStatus<H>
+where
+    H: Handler<T, Ctx, Out> + Sync,
+{
+    type Future = H::Future;
+
+    fn accepts(&self, status: StatusCode) -> bool {
+        self.statuses.accepts(status)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.handler.name()
+    }
+
+    fn handle(self, resp: Arc<Response>, ctx: Ctx) -> Self::Future {
+        self.handler.handle(resp, ctx)
+    }
+}
+
+/// A handler that only runs for urls queued with a matching
+/// [`crate::next_action::NextUrl::callback`].
+#[derive(Clone)]
+pub struct Named<H> {
+    name: Cow<'static, str>,
+    handler: H,
+}
+
+impl<H> Named<H> {
+    pub fn new(name: impl Into<Cow<'static, str>>, handler: H) -> Self {
+        Self {
+            name: name.into(),
+            handler,
+        }
+    }
+}
+
+impl<H, T, Ctx, Out> Handler<T, Ctx, Out> for Named<H>
+where
+    H: Handler<T, Ctx, Out> + Sync,
+{
+    type Future = H::Future;
+
+    fn accepts(&self, status: StatusCode) -> bool {
+        self.handler.accepts(status)
+    }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn handle(self, resp: Arc<Response>, ctx: Ctx) -> Self::Future {
+        self.handler.handle(resp, ctx)
+    }
+}
+
 pub struct HandlerPair<Ctx, Out, T1, T2>(T1, T2, PhantomData<fn() -> (Ctx, Out)>)
 where
     T1: HandlerWrapper<Ctx, Out>,
@@ -23,14 +157,49 @@ where
     Ctx: Clone + Send + Sync,
     Out: Send,
 {
-    fn handle(&self, resp: Arc<Response>, ctx: Ctx) -> BoxFuture<'_, NextActionVector<Ctx, Out>> {
+    fn handle(
+        &self,
+        resp: Arc<Response>,
+        ctx: Ctx,
+    ) -> BoxFuture<'_, (NextActionVector<Ctx, Out>, Vec<Rejection>)> {
         let fut1 = self.0.handle(resp.clone(), ctx.clone());
         let fut2 = self.1.handle(resp, ctx);
+        Box::pin(async move {
+            let ((mut actions, mut rejections), (actions2, rejections2)) =
+                futures::join!(fut1, fut2);
+            actions.extend(actions2);
+            rejections.extend(rejections2);
+            (actions, rejections)
+        })
+    }
+
+    fn handle_error(
+        &self,
+        error: FetchError,
+        ctx: Ctx,
+    ) -> BoxFuture<'_, NextActionVector<Ctx, Out>> {
+        let fut1 = self.0.handle_error(error.clone(), ctx.clone());
+        let fut2 = self.1.handle_error(error, ctx);
         Box::pin(async move { join_all([fut1, fut2]).await.into_iter().flatten().collect() })
     }
 }
 pub trait HandlerWrapper<Ctx, Out> {
-    fn handle(&self, resp: Arc<Response>, ctx: Ctx) -> BoxFuture<'_, NextActionVector<Ctx, Out>>;
+    /// Runs every handler that accepts `resp`, returning their actions and
+    /// the handlers that were skipped.
+    fn handle(
+        &self,
+        resp: Arc<Response>,
+        ctx: Ctx,
+    ) -> BoxFuture<'_, (NextActionVector<Ctx, Out>, Vec<Rejection>)>;
+
+    /// Called with non-2xx responses and failed requests.
+    fn handle_error(
+        &self,
+        _error: FetchError,
+        _ctx: Ctx,
+    ) -> BoxFuture<'_, NextActionVector<Ctx, Out>> {
+        Box::pin(async { Vec::new() })
+    }
 
     fn pair<T>(self, other: T) -> HandlerPair<Ctx, Out, T, Self>
     where
@@ -46,13 +215,65 @@ where
     Ctx: Send,
     H: Handler<T, Ctx, Out> + Send,
 {
-    fn handle(&self, resp: Arc<Response>, ctx: Ctx) -> BoxFuture<'_, NextActionVector<Ctx, Out>> {
+    fn handle(
+        &self,
+        resp: Arc<Response>,
+        ctx: Ctx,
+    ) -> BoxFuture<'_, (NextActionVector<Ctx, Out>, Vec<Rejection>)> {
+        if !self.inner.accepts(resp.status) || resp.callback.as_deref() != self.inner.name() {
+            return Box::pin(async { (Vec::new(), Vec::new()) });
+        }
         let fut = self.inner.clone();
-        let fut = async move { fut.handle(resp, ctx).await };
+        let fut = async move {
+            match fut.handle(resp, ctx).await {
+                Ok(actions) => (actions, Vec::new()),
+                Err(rejection) => (Vec::new(), vec![rejection]),
+            }
+        };
         Box::pin(fut)
     }
 }
 
+/// Wraps a closure given to [`crate::website::WebsiteBuilder::on_error`].
+pub struct ErrorHandlerBox<F, Ctx, Out> {
+    inner: F,
+    _marker: PhantomData<fn() -> (Ctx, Out)>,
+}
+
+impl<F, Ctx, Out> ErrorHandlerBox<F, Ctx, Out> {
+    pub(crate) fn from_fn(f: F) -> Self {
+        Self {
+            inner: f,
+            _marker: Default::default(),
+        }
+    }
+}
+
+impl<F, Fut, FutOut, Ctx, Out> HandlerWrapper<Ctx, Out> for ErrorHandlerBox<F, Ctx, Out>
+where
+    F: Fn(FetchError, Ctx) -> Fut + Send + Sync,
+    Fut: Future<Output = FutOut> + Send + 'static,
+    FutOut: IntoNextActionVec<Ctx, Out>,
+    Out: WebsiteOutput,
+{
+    fn handle(
+        &self,
+        _resp: Arc<Response>,
+        _ctx: Ctx,
+    ) -> BoxFuture<'_, (NextActionVector<Ctx, Out>, Vec<Rejection>)> {
+        Box::pin(async { (Vec::new(), Vec::new()) })
+    }
+
+    fn handle_error(
+        &self,
+        error: FetchError,
+        ctx: Ctx,
+    ) -> BoxFuture<'_, NextActionVector<Ctx, Out>> {
+        let fut = (self.inner)(error, ctx);
+        Box::pin(async move { fut.await.into_next_action_vec() })
+    }
+}
+
 pub struct HandlerBox<H, T, Ctx, Out>
 where
     H: Handler<T, Ctx, Out> + Send,
@@ -74,7 +295,20 @@ where
 }
 
 pub trait Handler<T, Ctx, Out>: Send + Clone {
-    type Future: Future<Output = NextActionVector<Ctx, Out>> + Send + 'static;
+    type Future: Future<Output = Result<NextActionVector<Ctx, Out>, Rejection>> + Send + 'static;
+
+    /// Whether to run for a response with `status`. Only 2xx by default;
+    /// see [`on_status`].
+    fn accepts(&self, status: StatusCode) -> bool {
+        status.is_success()
+    }
+
+    /// The callback name the handler answers to, `None` for the handlers
+    /// that run by default.
+    fn name(&self) -> Option<&str> {
+        None
+    }
+
     fn handle(self, resp: Arc<Response>, ctx: Ctx) -> Self::Future;
 }
 
@@ -86,10 +320,11 @@ where
     Out: WebsiteOutput + 'static,
     Ctx: 'static,
 {
-    type Future = Pin<Box<dyn Future<Output = NextActionVector<Ctx, Out>> + Send>>;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<NextActionVector<Ctx, Out>, Rejection>> + Send>>;
 
     fn handle(self, _resp: Arc<Response>, _ctx: Ctx) -> Self::Future {
-        Box::pin(async move { self().await.into_next_action_vec() })
+        Box::pin(async move { Ok(self().await.into_next_action_vec()) })
     }
 }
 
@@ -108,18 +343,20 @@ macro_rules! impl_handler {
             Ctx: 'static + Send + Sync,
             $( $ty: FromResponse<Ctx> + Send, )*
         {
-            type Future = Pin<Box<dyn Future<Output = NextActionVector<Ctx, Out>> + Send>>;
+            type Future = Pin<Box<dyn Future<Output = Result<NextActionVector<Ctx, Out>, Rejection>> + Send>>;
 
             fn handle(self, resp: Arc<Response>, ctx: Ctx) -> Self::Future {
                 Box::pin(async move {
                     let ctx = ctx;
                     $(
                         let $ty = match $ty::from_response(resp.as_ref(), &ctx).await {
-                            Some($ty) => $ty,
-                            _ => { return Vec::new() }
+                            Ok($ty) => $ty,
+                            Err(reason) => {
+                                return Err(Rejection::new::<F, $ty, Ctx>(&resp, reason));
+                            }
                         };
                     )*
-                    self($($ty,)*).await.into_next_action_vec()
+                    Ok(self($($ty,)*).await.into_next_action_vec())
                 })
             }
         }
@@ -148,3 +385,81 @@ macro_rules! all_the_tuples {
     };
 }
 all_the_tuples!(impl_handler);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_status_filter() {
+        let filter = StatusFilter::default();
+        assert!(filter.accepts(StatusCode::OK));
+        assert!(!filter.accepts(StatusCode::NOT_FOUND));
+
+        let filter =
+            StatusFilter::codes([StatusCode::NOT_FOUND]).or(StatusFilter::range(500..=599));
+        assert!(filter.accepts(StatusCode::NOT_FOUND));
+        assert!(filter.accepts(StatusCode::BAD_GATEWAY));
+        assert!(!filter.accepts(StatusCode::GONE));
+        assert!(!StatusFilter::errors().accepts(StatusCode::NO_CONTENT));
+        assert!(StatusFilter::any().accepts(StatusCode::NO_CONTENT));
+    }
+
+    #[cfg(feature = "extractor")]
+    #[tokio::test]
+    async fn test_rejection() {
+        use crate::{
+            extractor::{Json, JsonRejection},
+            next_action::NextAction,
+        };
+
+        struct Out;
+        impl WebsiteOutput for Out {
+            fn should_process(&self) -> bool {
+                true
+            }
+        }
+
+        async fn parse(Json(n): Json<u32>) -> Vec<NextAction<(), Out>> {
+            assert_eq!(n, 7);
+            Vec::new()
+        }
+
+        async fn lenient(
+            n: Option<Json<u32>>,
+            m: Result<Json<u32>, JsonRejection>,
+        ) -> Vec<NextAction<(), Out>> {
+            assert_eq!(n.is_some(), m.is_ok());
+            Vec::new()
+        }
+
+        let url = Url::parse("https://example.com/n").unwrap();
+        let resp = |body: &'static str| {
+            Arc::new(Response {
+                bytes: body.into(),
+                url: url.clone(),
+                original_url: url.clone(),
+                redirects: Vec::new(),
+                status: StatusCode::OK,
+                headers: Default::default(),
+                version: Default::default(),
+                depth: 0,
+                params: Default::default(),
+                callback: None,
+                state: Default::default(),
+                dom: Default::default(),
+            })
+        };
+        let handler = HandlerBox::from_handler(parse);
+        let (_, rejections) = handler.handle(resp("7"), ()).await;
+        assert!(rejections.is_empty());
+        let (_, rejections) = handler.handle(resp("seven"), ()).await;
+        assert_eq!(rejections.len(), 1);
+        assert!(rejections[0].handler.ends_with("parse"));
+        assert!(rejections[0].extractor.contains("Json<u32>"));
+
+        let handler = HandlerBox::from_handler(lenient);
+        let (_, rejections) = handler.handle(resp("seven"), ()).await;
+        assert!(rejections.is_empty());
+    }
+}
@@ -1,13 +1,28 @@
-#![feature(async_fn_in_trait)]
-#![allow(suspicious_auto_trait_impls)]
 #![allow(clippy::new_ret_no_self)]
 #![allow(clippy::type_complexity)]
+#![allow(clippy::large_enum_variant)]
 
+pub mod budget;
 pub mod client;
+pub mod control;
+pub mod dedup;
 pub mod error;
+pub mod fingerprint;
+mod frontier;
 pub mod handler;
 pub mod next_action;
+pub mod order;
 pub mod response;
+pub mod retry;
+pub mod robots;
+pub mod route;
+pub mod scope;
+mod spill;
+mod state;
+pub mod stats;
+#[cfg(test)]
+mod test_server;
+pub mod throttle;
 pub mod website;
 
 #[cfg(feature = "extractor")]
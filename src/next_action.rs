@@ -1,4 +1,9 @@
-use reqwest::Url;
+use std::{borrow::Cow, time::Duration};
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue, IntoHeaderName, CONTENT_TYPE},
+    Method, Url,
+};
 
 pub(crate) type NextActionVector<Data, Output> = Vec<NextAction<Data, Output>>;
 
@@ -6,15 +11,104 @@ pub trait WebsiteOutput {
     fn should_process(&self) -> bool;
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct NextUrl<Data> {
     pub(crate) url: Url,
     pub(crate) data: Data,
+    pub(crate) method: Method,
+    pub(crate) headers: HeaderMap,
+    pub(crate) body: Option<Vec<u8>>,
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) callback: Option<Cow<'static, str>>,
+    pub(crate) priority: i64,
+    pub(crate) attempt: u32,
+    pub(crate) depth: usize,
+    pub(crate) external_hops: usize,
 }
 
 impl<Data> NextUrl<Data> {
-    fn new(url: Url, data: Data) -> Self {
-        Self { url, data }
+    pub fn new(url: Url, data: Data) -> Self {
+        Self {
+            url,
+            data,
+            method: Method::GET,
+            headers: Default::default(),
+            body: None,
+            timeout: None,
+            callback: None,
+            priority: 0,
+            attempt: 0,
+            depth: 0,
+            external_hops: 0,
+        }
+    }
+
+    /// Urls with a higher priority are visited first, whatever the
+    /// [`crate::order::CrawlOrder`]. Defaults to 0.
+    pub fn priority(mut self, priority: i64) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Defaults to `GET`. Part of the dedup fingerprint.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Adds a header, replacing any previous value for `name`. Only
+    /// [`crate::fingerprint::FINGERPRINT_HEADERS`] are part of the default
+    /// dedup fingerprint.
+    pub fn header<K: IntoHeaderName>(mut self, name: K, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.headers.extend(headers);
+        self
+    }
+
+    /// Part of the dedup fingerprint, so different bodies sent to the same
+    /// url are all fetched.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sets a url-encoded body and its content type.
+    #[cfg(feature = "serde")]
+    pub fn form<T: serde::Serialize + ?Sized>(self, form: &T) -> crate::error::Result<Self> {
+        let body = serde_urlencoded::to_string(form)?;
+        Ok(self
+            .header(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/x-www-form-urlencoded"),
+            )
+            .body(body))
+    }
+
+    /// Sets a JSON body and its content type.
+    #[cfg(feature = "serde")]
+    pub fn json<T: serde::Serialize + ?Sized>(self, json: &T) -> crate::error::Result<Self> {
+        let body = serde_json::to_vec(json)?;
+        Ok(self
+            .header(CONTENT_TYPE, HeaderValue::from_static("application/json"))
+            .body(body))
+    }
+
+    /// Overrides the client timeout for this request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Only runs the handlers registered under `name` with
+    /// [`crate::website::WebsiteBuilder::named`] on the response, instead of
+    /// the unnamed ones. Unknown names fall back to the unnamed handlers.
+    pub fn callback(mut self, name: impl Into<Cow<'static, str>>) -> Self {
+        self.callback = Some(name.into());
+        self
     }
 }
 
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use reqwest::Url;
+
+use crate::next_action::NextUrl;
+
+/// The order in which a website visits queued urls.
+///
+/// An explicit [`NextUrl::priority`] always comes first, higher first; the
+/// order only decides between urls of equal priority. Hosts that are equally
+/// ranked are still served round-robin.
+#[derive(Clone, Default)]
+pub enum CrawlOrder {
+    /// Shallow urls first, then in discovery order.
+    #[default]
+    BreadthFirst,
+    /// Deep urls first, most recently discovered first.
+    DepthFirst,
+    /// Higher scores first, then in discovery order. Called with the url and
+    /// its depth.
+    Custom(Arc<dyn Fn(&Url, usize) -> i64 + Send + Sync>),
+}
+
+impl CrawlOrder {
+    pub fn custom<F>(score: F) -> Self
+    where
+        F: Fn(&Url, usize) -> i64 + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(score))
+    }
+
+    /// `seq` grows with every push, so newer urls get larger values.
+    pub(crate) fn rank<Ctx>(&self, next: &NextUrl<Ctx>, seq: u64) -> Rank {
+        let seq = seq as i64;
+        let (score, seq) = match self {
+            Self::BreadthFirst => (-(next.depth as i64), -seq),
+            Self::DepthFirst => (next.depth as i64, seq),
+            Self::Custom(score) => (score(&next.url, next.depth), -seq),
+        };
+        Rank {
+            priority: next.priority,
+            score,
+            seq,
+        }
+    }
+}
+
+/// Where a url sits in the frontier; larger ranks are popped first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Rank {
+    pub(crate) priority: i64,
+    pub(crate) score: i64,
+    pub(crate) seq: i64,
+}
+
+impl Rank {
+    /// The part of the rank that is compared across hosts.
+    pub(crate) fn level(&self) -> (i64, i64) {
+        (self.priority, self.score)
+    }
+}
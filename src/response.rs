@@ -1,29 +1,200 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    borrow::Cow,
+    collections::HashMap,
+    convert::Infallible,
+    fmt::{self, Display},
+    result::Result as StdResult,
+    str::Utf8Error,
+    sync::{Arc, Mutex, OnceLock, PoisonError},
+};
+
 use async_trait::async_trait;
+use bytes::Bytes;
 use serde::de::DeserializeOwned;
 
-use reqwest::{Response as ReqwestResponse, Url};
+use reqwest::{header::HeaderMap, Response as ReqwestResponse, StatusCode, Url, Version};
+use scraper::Html;
 
 use crate::error::{self, Result};
 
+/// One hop of a redirect chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redirect {
+    pub from: Url,
+    pub to: Url,
+    pub status: StatusCode,
+}
+
+/// Values shared by every handler of a website, one per type, see
+/// [`crate::website::WebsiteBuilder::with_state`].
+#[derive(Clone, Default)]
+pub struct AppState(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl AppState {
+    pub(crate) fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.0.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.0.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+}
+
 #[derive(Clone)]
 pub struct Response {
-    pub bytes: Vec<u8>,
+    pub bytes: Bytes,
+    /// The url the body was fetched from, after redirects.
     pub url: Url,
+    /// The url that was requested, before redirects.
+    pub original_url: Url,
+    pub redirects: Vec<Redirect>,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub version: Version,
+    pub depth: usize,
+    /// What the matching route captured, see [`crate::route::Pattern`].
+    pub params: HashMap<String, String>,
+    /// The handler name the url was queued for, see [`crate::next_action::NextUrl::callback`].
+    pub callback: Option<Cow<'static, str>>,
+    pub state: Arc<AppState>,
+    /// The parsed body, shared with every clone of the response.
+    pub(crate) dom: Arc<OnceLock<StdResult<Arc<Mutex<Html>>, Utf8Error>>>,
 }
 
 impl Response {
     pub(crate) async fn from_reqwest(value: ReqwestResponse) -> error::Result<Self> {
+        let url = value.url().clone();
+        let status = value.status();
+        let headers = value.headers().clone();
+        let version = value.version();
         Ok(Self {
-            url: value.url().clone(),
-            bytes: value.bytes().await?.to_vec(),
+            original_url: url.clone(),
+            url,
+            redirects: Vec::new(),
+            status,
+            headers,
+            version,
+            bytes: value.bytes().await?,
+            depth: 0,
+            params: HashMap::new(),
+            callback: None,
+            state: Default::default(),
+            dom: Default::default(),
         })
     }
 
     pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
         serde_json::from_slice(&self.bytes).map_err(|err| err.into())
     }
+
+    /// The body parsed as an HTML document. Parsed on first use, then
+    /// shared by every extractor and handler of the response.
+    pub fn dom(&self) -> StdResult<Arc<Mutex<Html>>, Utf8Error> {
+        self.dom
+            .get_or_init(|| {
+                let body = std::str::from_utf8(&self.bytes)?;
+                Ok(Arc::new(Mutex::new(Html::parse_document(body))))
+            })
+            .clone()
+    }
+
+    /// Runs `f` on the parsed body, see [`Response::dom`].
+    pub fn with_dom<R>(&self, f: impl FnOnce(&Html) -> R) -> StdResult<R, Utf8Error> {
+        let dom = self.dom()?;
+        let dom = dom.lock().unwrap_or_else(PoisonError::into_inner);
+        Ok(f(&dom))
+    }
 }
+
+#[cfg(test)]
+impl Response {
+    /// A 200 response from `url` with `body`, for extractor tests.
+    pub(crate) fn test(url: &str, body: &'static str) -> Self {
+        let url = Url::parse(url).unwrap();
+        Self {
+            bytes: body.into(),
+            original_url: url.clone(),
+            url,
+            redirects: Vec::new(),
+            status: StatusCode::OK,
+            headers: Default::default(),
+            version: Default::default(),
+            depth: 0,
+            params: Default::default(),
+            callback: None,
+            state: Default::default(),
+            dom: Default::default(),
+        }
+    }
+}
+
 #[async_trait]
 pub trait FromResponse<Ctx>: Sized {
-    async fn from_response(resp: &Response, ctx: &Ctx) -> Option<Self>;
+    /// Why the value could not be extracted. The handler is skipped with a
+    /// [`Rejection`].
+    type Rejection: std::error::Error + Send + Sync + 'static;
+
+    async fn from_response(resp: &Response, ctx: &Ctx) -> StdResult<Self, Self::Rejection>;
+}
+
+/// Runs the handler with `None` instead of skipping it when `T` fails.
+#[async_trait]
+impl<T, Ctx> FromResponse<Ctx> for Option<T>
+where
+    T: FromResponse<Ctx>,
+    Ctx: Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, ctx: &Ctx) -> StdResult<Self, Self::Rejection> {
+        Ok(T::from_response(resp, ctx).await.ok())
+    }
+}
+
+/// Runs the handler with the rejection instead of skipping it when `T`
+/// fails.
+#[async_trait]
+impl<T, Ctx> FromResponse<Ctx> for StdResult<T, T::Rejection>
+where
+    T: FromResponse<Ctx>,
+    Ctx: Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_response(resp: &Response, ctx: &Ctx) -> StdResult<Self, Self::Rejection> {
+        Ok(T::from_response(resp, ctx).await)
+    }
+}
+
+/// A handler that was skipped because one of its extractors failed.
+#[derive(Debug, Clone)]
+pub struct Rejection {
+    /// The type name of the handler.
+    pub handler: &'static str,
+    /// The type name of the extractor that failed.
+    pub extractor: &'static str,
+    pub url: Url,
+    pub reason: Arc<dyn std::error::Error + Send + Sync>,
+}
+
+impl Rejection {
+    pub(crate) fn new<H, E: FromResponse<Ctx>, Ctx>(resp: &Response, reason: E::Rejection) -> Self {
+        Self {
+            handler: type_name::<H>(),
+            extractor: type_name::<E>(),
+            url: resp.url.clone(),
+            reason: Arc::new(reason),
+        }
+    }
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} skipped {}: {} failed with {}",
+            self.handler, self.url, self.extractor, self.reason
+        )
+    }
 }
@@ -0,0 +1,206 @@
+use std::time::{Duration, SystemTime};
+
+use rand::Rng;
+use reqwest::{header::HeaderValue, StatusCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    Connect,
+    Timeout,
+    Status(StatusCode),
+    RetryAfter(StatusCode, Duration),
+    Other,
+}
+
+impl Failure {
+    pub(crate) fn from_reqwest(err: &reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else if err.is_connect() {
+            Self::Connect
+        } else if let Some(status) = err.status() {
+            Self::Status(status)
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Parses a `Retry-After` value given either in seconds or as an HTTP date.
+pub(crate) fn parse_retry_after(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// The delay after the `n`th failed attempt is `base_delay * 2^(n - 1)`,
+/// capped at `max_delay` and scaled by a random factor in `1 ± jitter`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_retry_after: Duration,
+    jitter: f64,
+    on_connect: bool,
+    on_timeout: bool,
+    on_server_error: bool,
+    on_too_many_requests: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_retry_after: Duration::from_secs(300),
+            jitter: 0.5,
+            on_connect: true,
+            on_timeout: true,
+            on_server_error: true,
+            on_too_many_requests: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// The longest a `Retry-After` can pause a host. Defaults to 5 minutes.
+    pub fn max_retry_after(mut self, delay: Duration) -> Self {
+        self.max_retry_after = delay;
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn retry_connect(mut self, retry: bool) -> Self {
+        self.on_connect = retry;
+        self
+    }
+
+    pub fn retry_timeout(mut self, retry: bool) -> Self {
+        self.on_timeout = retry;
+        self
+    }
+
+    pub fn retry_server_error(mut self, retry: bool) -> Self {
+        self.on_server_error = retry;
+        self
+    }
+
+    pub fn retry_too_many_requests(mut self, retry: bool) -> Self {
+        self.on_too_many_requests = retry;
+        self
+    }
+
+    fn is_retryable(&self, failure: &Failure) -> bool {
+        match failure {
+            Failure::Connect => self.on_connect,
+            Failure::Timeout => self.on_timeout,
+            Failure::Status(StatusCode::TOO_MANY_REQUESTS) => self.on_too_many_requests,
+            Failure::Status(status) => status.is_server_error() && self.on_server_error,
+            Failure::RetryAfter(status, _) => self.is_retryable(&Failure::Status(*status)),
+            Failure::Other => false,
+        }
+    }
+
+    pub(crate) fn retry_after(&self, delay: Duration) -> Duration {
+        delay.min(self.max_retry_after)
+    }
+
+    /// `attempt` is the number of attempts made so far.
+    pub(crate) fn will_retry(&self, failure: &Failure, attempt: u32) -> bool {
+        attempt < self.max_attempts && self.is_retryable(failure)
+    }
+
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exp = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(exp).min(self.max_delay);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+        let factor = rand::thread_rng().gen_range(1.0 - self.jitter..=1.0 + self.jitter);
+        delay.mul_f64(factor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = RetryPolicy::default()
+            .max_attempts(10)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_secs(1))
+            .jitter(0.0);
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(400));
+        assert_eq!(policy.backoff(9), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_will_retry() {
+        let policy = RetryPolicy::default()
+            .max_attempts(2)
+            .retry_server_error(false);
+        assert!(policy.will_retry(&Failure::Connect, 1));
+        assert!(!policy.will_retry(&Failure::Connect, 2));
+        assert!(policy.will_retry(&Failure::Status(StatusCode::TOO_MANY_REQUESTS), 1));
+        assert!(!policy.will_retry(&Failure::Status(StatusCode::BAD_GATEWAY), 1));
+        assert!(!policy.will_retry(&Failure::Status(StatusCode::NOT_FOUND), 1));
+
+        let retry_after = |status| Failure::RetryAfter(status, Duration::from_secs(1));
+        assert!(policy.will_retry(&retry_after(StatusCode::TOO_MANY_REQUESTS), 1));
+        assert!(!policy.will_retry(&retry_after(StatusCode::TOO_MANY_REQUESTS), 2));
+        assert!(!policy.will_retry(&retry_after(StatusCode::SERVICE_UNAVAILABLE), 1));
+        assert_eq!(
+            policy.retry_after(Duration::from_secs(86400)),
+            Duration::from_secs(300)
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let secs = HeaderValue::from_static("120");
+        assert_eq!(parse_retry_after(&secs), Some(Duration::from_secs(120)));
+        let past = HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(parse_retry_after(&past), Some(Duration::ZERO));
+        let garbage = HeaderValue::from_static("soon");
+        assert_eq!(parse_retry_after(&garbage), None);
+    }
+}
@@ -0,0 +1,331 @@
+use std::{sync::Arc, time::Duration};
+
+use log::{debug, warn};
+use reqwest::{redirect, Url};
+use tokio::sync::OnceCell;
+
+/// What to assume when `/robots.txt` cannot be fetched because of a network
+/// error or a 5xx response. A 4xx response always means there are no rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RobotsFallback {
+    #[default]
+    AllowAll,
+    DenyAll,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+impl Rule {
+    fn matches(&self, path: &str) -> bool {
+        let (pattern, anchored) = match self.pattern.strip_suffix('$') {
+            Some(pattern) => (pattern, true),
+            None => (self.pattern.as_str(), false),
+        };
+        wildcard_match(pattern.as_bytes(), path.as_bytes(), anchored)
+    }
+}
+
+/// Matches `*` greedily, backtracking only to the last star seen, so the
+/// work stays linear in the path for each star.
+pub(crate) fn wildcard_match(pattern: &[u8], path: &[u8], anchored: bool) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // The last star in the pattern, and where in the path it stops matching.
+    let mut star = None;
+    while s < path.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, s));
+                p += 1;
+            }
+            Some(&c) if c == path[s] => {
+                p += 1;
+                s += 1;
+            }
+            None if !anchored => return true,
+            _ => match star {
+                Some((star_p, star_s)) => {
+                    star = Some((star_p, star_s + 1));
+                    p = star_p + 1;
+                    s = star_s + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// The rules of a `robots.txt` that apply to one user agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Robots {
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+impl Robots {
+    pub fn allow_all() -> Self {
+        Default::default()
+    }
+
+    pub fn deny_all() -> Self {
+        Self {
+            rules: vec![Rule {
+                allow: false,
+                pattern: "/".to_string(),
+            }],
+            crawl_delay: None,
+        }
+    }
+
+    fn fallback(fallback: RobotsFallback) -> Self {
+        match fallback {
+            RobotsFallback::AllowAll => Self::allow_all(),
+            RobotsFallback::DenyAll => Self::deny_all(),
+        }
+    }
+
+    /// Picks the groups naming `user_agent`, or the `*` groups if none do.
+    pub fn parse(body: &str, user_agent: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut in_agents = false;
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        groups.push(Default::default());
+                        in_agents = true;
+                    }
+                    let group = groups.last_mut().unwrap();
+                    group.agents.push(value.to_ascii_lowercase());
+                }
+                key @ ("allow" | "disallow") => {
+                    in_agents = false;
+                    let Some(group) = groups.last_mut() else {
+                        continue;
+                    };
+                    // An empty `Disallow` allows everything and adds no rule.
+                    if value.is_empty() {
+                        continue;
+                    }
+                    group.rules.push(Rule {
+                        allow: key == "allow",
+                        pattern: value.to_string(),
+                    });
+                }
+                "crawl-delay" => {
+                    in_agents = false;
+                    let Some(group) = groups.last_mut() else {
+                        continue;
+                    };
+                    group.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                        .map(Duration::from_secs_f64);
+                }
+                _ => {}
+            }
+        }
+
+        let user_agent = user_agent.to_ascii_lowercase();
+        let token = user_agent
+            .split(|c: char| c == '/' || c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        let named = |group: &&Group| {
+            group
+                .agents
+                .iter()
+                .any(|agent| agent != "*" && token == agent)
+        };
+        let wildcard = |group: &&Group| group.agents.iter().any(|agent| agent == "*");
+        let selected: Vec<&Group> = if groups.iter().any(|g| named(&g)) {
+            groups.iter().filter(named).collect()
+        } else {
+            groups.iter().filter(wildcard).collect()
+        };
+
+        Self {
+            rules: selected
+                .iter()
+                .flat_map(|group| group.rules.iter().cloned())
+                .collect(),
+            crawl_delay: selected.iter().find_map(|group| group.crawl_delay),
+        }
+    }
+
+    /// The longest matching rule wins, and `Allow` wins a tie.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(&path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+pub(crate) struct RobotsCache {
+    /// Separate from the page client, which follows redirects by hand:
+    /// `robots.txt` often redirects to https or another host.
+    client: reqwest::Client,
+    user_agent: String,
+    fallback: RobotsFallback,
+    origins: scc::HashMap<String, Arc<OnceCell<Arc<Robots>>>>,
+}
+
+impl RobotsCache {
+    pub(crate) fn new(user_agent: String, fallback: RobotsFallback) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .user_agent(&user_agent)
+                .redirect(redirect::Policy::limited(5))
+                .build()
+                .unwrap(),
+            user_agent,
+            fallback,
+            origins: Default::default(),
+        }
+    }
+
+    /// Returns the rules for `url`'s origin, fetching them on first use.
+    pub(crate) async fn get(&self, url: &Url) -> Arc<Robots> {
+        let origin = url.origin().ascii_serialization();
+        let cell = self
+            .origins
+            .entry_async(origin.clone())
+            .await
+            .or_default()
+            .get()
+            .clone();
+        cell.get_or_init(|| async { Arc::new(self.fetch(&origin).await) })
+            .await
+            .clone()
+    }
+
+    /// The rules for `url`'s origin, if they have been fetched already.
+    pub(crate) fn cached(&self, url: &Url) -> Option<Arc<Robots>> {
+        let origin = url.origin().ascii_serialization();
+        self.origins.read(&origin, |_, cell| cell.get().cloned())?
+    }
+
+    async fn fetch(&self, origin: &str) -> Robots {
+        let url = format!("{}/robots.txt", origin);
+        let resp = match self.client.get(&url).send().await {
+            Ok(resp) => resp,
+            Err(err) => {
+                warn!(
+                    "unable to fetch {}: {}, using {:?}",
+                    url, err, self.fallback
+                );
+                return Robots::fallback(self.fallback);
+            }
+        };
+        let status = resp.status();
+        if status.is_client_error() {
+            debug!("{} responded with {}, allowing all", url, status);
+            return Robots::allow_all();
+        }
+        if !status.is_success() {
+            warn!(
+                "{} responded with {}, using {:?}",
+                url, status, self.fallback
+            );
+            return Robots::fallback(self.fallback);
+        }
+        match resp.text().await {
+            Ok(body) => Robots::parse(&body, &self.user_agent),
+            Err(err) => {
+                warn!("unable to read {}: {}, using {:?}", url, err, self.fallback);
+                Robots::fallback(self.fallback)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ROBOTS: &str = r#"
+User-agent: *
+Disallow: /private
+Allow: /private/public
+Crawl-delay: 2
+
+# syphon specific rules
+User-agent: Syphon
+User-agent: other
+Disallow: /*.pdf$
+Disallow: /search?
+Crawl-delay: 0.5
+"#;
+
+    fn url(path: &str) -> Url {
+        Url::parse("https://example.com")
+            .unwrap()
+            .join(path)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wildcard_group() {
+        let robots = Robots::parse(ROBOTS, "somebot/1.0");
+        assert!(robots.is_allowed(&url("/")));
+        assert!(!robots.is_allowed(&url("/private/page")));
+        assert!(robots.is_allowed(&url("/private/public/page")));
+        assert_eq!(robots.crawl_delay(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_named_group() {
+        let robots = Robots::parse(ROBOTS, "syphon/0.1");
+        assert!(robots.is_allowed(&url("/private/page")));
+        assert!(!robots.is_allowed(&url("/doc.pdf")));
+        assert!(robots.is_allowed(&url("/doc.pdf?download=1")));
+        assert!(!robots.is_allowed(&url("/search?q=rust")));
+        assert!(robots.is_allowed(&url("/search")));
+        assert_eq!(robots.crawl_delay(), Some(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_empty_disallow() {
+        let robots = Robots::parse("User-agent: *\nDisallow:\n", "syphon");
+        assert!(robots.is_allowed(&url("/anything")));
+        assert!(!Robots::deny_all().is_allowed(&url("/anything")));
+    }
+
+    #[test]
+    fn test_many_wildcards() {
+        let robots = Robots::parse("User-agent: *\nDisallow: /*a*a*a*a*a*a*b\n", "syphon");
+        let path = format!("/{}", "a".repeat(4096));
+        assert!(robots.is_allowed(&url(&path)));
+        assert!(!robots.is_allowed(&url(&format!("{}b", path))));
+        assert!(wildcard_match(b"*a*b", b"xaxxbyb", true));
+        assert!(!wildcard_match(b"*a*b", b"xaxxby", true));
+        assert!(wildcard_match(b"/a*", b"/a", true));
+    }
+}
@@ -0,0 +1,175 @@
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use reqwest::{StatusCode, Url};
+
+use crate::{
+    handler::Handler,
+    next_action::NextActionVector,
+    response::{Rejection, Response},
+};
+
+/// Which urls a routed handler runs for.
+///
+/// A string is matched against the whole url path:
+/// - `{name}` captures one path segment and `{*name}` captures the rest of
+///   the path;
+/// - `*` matches within a segment and `**` matches across segments.
+///
+/// A [`Regex`] is searched in the full url and its named groups are
+/// captured as they appear in the url, without decoding.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+    path: bool,
+}
+
+impl Pattern {
+    /// # Panics
+    ///
+    /// If a capture name is not made of letters, digits and underscores.
+    pub fn new(pattern: &str) -> Self {
+        let mut regex = String::from("^");
+        let mut rest = pattern;
+        while let Some(c) = rest.chars().next() {
+            if let Some((name, after)) = rest.strip_prefix('{').and_then(|s| s.split_once('}')) {
+                let (name, capture) = match name.strip_prefix('*') {
+                    Some(name) => (name, ".*"),
+                    None => (name, "[^/]+"),
+                };
+                if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    panic!(
+                        "invalid capture {{{}}} in route pattern {:?}",
+                        name, pattern
+                    );
+                }
+                regex.push_str(&format!("(?P<{}>{})", name, capture));
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("**") {
+                regex.push_str(".*");
+                rest = after;
+            } else {
+                match c {
+                    '*' => regex.push_str("[^/]*"),
+                    c => regex.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+                }
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        regex.push('$');
+        Self {
+            regex: Regex::new(&regex).expect("route pattern compiles to a valid regex"),
+            path: true,
+        }
+    }
+
+    /// The captures of `url`, or `None` if it does not match.
+    pub fn captures(&self, url: &Url) -> Option<HashMap<String, String>> {
+        let haystack = if self.path { url.path() } else { url.as_str() };
+        let captures = self.regex.captures(haystack)?;
+        Some(
+            self.regex
+                .capture_names()
+                .flatten()
+                .filter_map(|name| {
+                    let value = captures.name(name)?.as_str();
+                    let value = match self.path {
+                        true => percent_decode_str(value).decode_utf8_lossy().into_owned(),
+                        false => value.to_string(),
+                    };
+                    Some((name.to_string(), value))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+impl From<Regex> for Pattern {
+    fn from(regex: Regex) -> Self {
+        Self { regex, path: false }
+    }
+}
+
+/// A handler that only runs for urls matching a [`Pattern`], with the
+/// captures available through [`crate::extractor::PathParams`].
+#[derive(Clone)]
+pub struct Route<H> {
+    pattern: Arc<Pattern>,
+    handler: H,
+}
+
+impl<H> Route<H> {
+    pub fn new(pattern: impl Into<Pattern>, handler: H) -> Self {
+        Self {
+            pattern: Arc::new(pattern.into()),
+            handler,
+        }
+    }
+}
+
+impl<H, T, Ctx, Out> Handler<T, Ctx, Out> for Route<H>
+where
+    H: Handler<T, Ctx, Out> + Sync,
+    Ctx: Send + 'static,
+    Out: Send + 'static,
+{
+    type Future =
+        Pin<Box<dyn Future<Output = Result<NextActionVector<Ctx, Out>, Rejection>> + Send>>;
+
+    fn accepts(&self, status: StatusCode) -> bool {
+        self.handler.accepts(status)
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.handler.name()
+    }
+
+    fn handle(self, resp: Arc<Response>, ctx: Ctx) -> Self::Future {
+        let Some(params) = self.pattern.captures(&resp.url) else {
+            return Box::pin(async { Ok(Vec::new()) });
+        };
+        let resp = Arc::new(Response {
+            params,
+            ..Response::clone(&resp)
+        });
+        Box::pin(self.handler.handle(resp, ctx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pattern() {
+        let url = |path: &str| Url::parse(&format!("https://example.com{}", path)).unwrap();
+
+        let pattern = Pattern::new("/wiki/{title}");
+        let params = pattern.captures(&url("/wiki/Caf%C3%A9")).unwrap();
+        assert_eq!(params["title"], "Café");
+        assert!(pattern.captures(&url("/wiki/a/b")).is_none());
+        assert!(pattern.captures(&url("/wiki/")).is_none());
+
+        let pattern = Pattern::new("/users/{id}/{*rest}");
+        let params = pattern.captures(&url("/users/42/posts/7")).unwrap();
+        assert_eq!((&*params["id"], &*params["rest"]), ("42", "posts/7"));
+
+        let pattern = Pattern::new("/docs/*.html");
+        assert!(pattern.captures(&url("/docs/intro.html")).is_some());
+        assert!(pattern.captures(&url("/docs/a/intro.html")).is_none());
+        assert!(Pattern::new("/docs/**.html")
+            .captures(&url("/docs/a/intro.html"))
+            .is_some());
+
+        let pattern = Pattern::from(Regex::new(r"\?page=(?P<page>\d+)").unwrap());
+        let params = pattern.captures(&url("/list?page=3")).unwrap();
+        assert_eq!(params["page"], "3");
+    }
+}
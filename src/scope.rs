@@ -0,0 +1,156 @@
+use reqwest::Url;
+
+use crate::robots::wildcard_match;
+
+#[derive(Debug, Clone)]
+enum ScopeKind {
+    SameHost,
+    Domains(Vec<String>),
+}
+
+/// Decides which discovered urls a website follows, relative to its
+/// starting urls.
+#[derive(Debug, Clone)]
+pub struct Scope {
+    kind: ScopeKind,
+    deny: Vec<String>,
+    external_depth: usize,
+    seed_hosts: Vec<String>,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self::same_host()
+    }
+}
+
+impl Scope {
+    fn new(kind: ScopeKind) -> Self {
+        Self {
+            kind,
+            deny: Vec::new(),
+            external_depth: 0,
+            seed_hosts: Vec::new(),
+        }
+    }
+
+    /// Only hosts of the starting urls.
+    pub fn same_host() -> Self {
+        Self::new(ScopeKind::SameHost)
+    }
+
+    /// Hosts matching any of `patterns`, where `*` matches any run of
+    /// characters, e.g. `["example.com", "*.example.com"]` for a site and its
+    /// subdomains.
+    pub fn domains<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(ScopeKind::Domains(lowercase(patterns)))
+    }
+
+    /// Hosts matching any of `patterns` are never followed, even as
+    /// external links.
+    pub fn deny<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.deny.extend(lowercase(patterns));
+        self
+    }
+
+    /// Follow out-of-scope links up to `depth` hops away from the last
+    /// in-scope page.
+    pub fn external_depth(mut self, depth: usize) -> Self {
+        self.external_depth = depth;
+        self
+    }
+
+    pub(crate) fn with_seeds(mut self, seeds: &[Url]) -> Self {
+        self.seed_hosts = seeds
+            .iter()
+            .filter_map(|url| url.host_str())
+            .map(|host| host.to_ascii_lowercase())
+            .collect();
+        self
+    }
+
+    fn is_denied(&self, host: &str) -> bool {
+        self.deny.iter().any(|pattern| glob(pattern, host))
+    }
+
+    fn contains(&self, host: &str) -> bool {
+        match &self.kind {
+            ScopeKind::SameHost => self.seed_hosts.iter().any(|seed| seed == host),
+            ScopeKind::Domains(patterns) => patterns.iter().any(|pattern| glob(pattern, host)),
+        }
+    }
+
+    /// Returns how many hops `url` is outside the scope, or `None` if it
+    /// should not be followed. `parent_hops` is the same count for the page
+    /// linking to `url`.
+    pub(crate) fn check(&self, url: &Url, parent_hops: usize) -> Option<usize> {
+        let host = url.host_str()?.to_ascii_lowercase();
+        if self.is_denied(&host) {
+            return None;
+        }
+        if self.contains(&host) {
+            return Some(0);
+        }
+        (parent_hops < self.external_depth).then_some(parent_hops + 1)
+    }
+}
+
+fn lowercase<I, S>(patterns: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    patterns
+        .into_iter()
+        .map(|x| x.into().to_ascii_lowercase())
+        .collect()
+}
+
+fn glob(pattern: &str, host: &str) -> bool {
+    wildcard_match(pattern.as_bytes(), host.as_bytes(), true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_scope_check() {
+        let seeds = [url("https://www.example.com/")];
+        let same_host = Scope::same_host().with_seeds(&seeds);
+        assert_eq!(
+            same_host.check(&url("https://www.example.com/a"), 0),
+            Some(0)
+        );
+        assert_eq!(same_host.check(&url("https://example.com/a"), 0), None);
+
+        let subdomains = Scope::domains(["example.com", "*.example.com"])
+            .deny(["ads.example.com"])
+            .with_seeds(&seeds);
+        assert_eq!(
+            subdomains.check(&url("https://api.example.com/"), 0),
+            Some(0)
+        );
+        assert_eq!(subdomains.check(&url("https://ads.example.com/"), 0), None);
+        assert_eq!(subdomains.check(&url("https://notexample.com/"), 0), None);
+
+        let domains = Scope::domains(["*.cdn.net", "example.com"])
+            .external_depth(1)
+            .with_seeds(&seeds);
+        assert_eq!(domains.check(&url("https://img.cdn.net/x"), 1), Some(0));
+        assert_eq!(domains.check(&url("https://other.org/"), 0), Some(1));
+        assert_eq!(domains.check(&url("https://other.org/"), 1), None);
+    }
+}
@@ -0,0 +1,166 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{error::Result, next_action::NextUrl, order::Rank, state::Codec};
+
+#[derive(PartialEq, Eq)]
+struct Spilled {
+    rank: Rank,
+    offset: u64,
+    len: u32,
+}
+
+impl PartialOrd for Spilled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Spilled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Urls that did not fit in the frontier's memory window, appended to a
+/// segment file with only their rank and position kept in memory.
+///
+/// The file is truncated whenever every spilled url has been read back and no
+/// [`SpillSnapshot`] is still reading it, and removed on drop.
+pub(crate) struct Spill<Ctx> {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    reader: File,
+    end: u64,
+    index: BinaryHeap<Spilled>,
+    codec: Codec<Ctx>,
+    snapshots: Arc<()>,
+    /// How many urls the frontier keeps in memory before spilling.
+    pub(crate) window: usize,
+}
+
+impl<Ctx> Spill<Ctx> {
+    pub(crate) fn new(dir: &Path, window: usize, codec: Codec<Ctx>) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("frontier-{}.segment", uuid::Uuid::new_v4()));
+        let writer = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(&path)?;
+        let reader = File::open(&path)?;
+        Ok(Self {
+            path,
+            writer: BufWriter::new(writer),
+            reader,
+            end: 0,
+            index: Default::default(),
+            codec,
+            snapshots: Arc::new(()),
+            window: window.max(1),
+        })
+    }
+
+    pub(crate) fn peek(&self) -> Option<Rank> {
+        self.index.peek().map(|spilled| spilled.rank)
+    }
+
+    pub(crate) fn push(&mut self, rank: Rank, next: &NextUrl<Ctx>) -> Result<()> {
+        let mut line = serde_json::to_vec(&self.codec.encode(next)?)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.index.push(Spilled {
+            rank,
+            offset: self.end,
+            len: line.len() as u32,
+        });
+        self.end += line.len() as u64;
+        Ok(())
+    }
+
+    fn read(&mut self, spilled: &Spilled) -> Result<NextUrl<Ctx>> {
+        self.writer.flush()?;
+        self.reader.seek(SeekFrom::Start(spilled.offset))?;
+        let mut line = vec![0; spilled.len as usize];
+        self.reader.read_exact(&mut line)?;
+        self.codec.decode(serde_json::from_slice(&line)?)
+    }
+
+    /// Reads back the highest ranked url.
+    pub(crate) fn pop(&mut self) -> Result<Option<(Rank, NextUrl<Ctx>)>> {
+        let Some(spilled) = self.index.pop() else {
+            return Ok(None);
+        };
+        let next = self.read(&spilled)?;
+        if self.index.is_empty() && Arc::strong_count(&self.snapshots) == 1 {
+            self.writer.flush()?;
+            self.writer.get_ref().set_len(0)?;
+            self.end = 0;
+        }
+        Ok(Some((spilled.rank, next)))
+    }
+
+    /// Positions of every spilled url, to be read back without holding the
+    /// frontier's lock.
+    pub(crate) fn snapshot(&mut self) -> Result<SpillSnapshot<Ctx>> {
+        self.writer.flush()?;
+        let mut entries: Vec<_> = self
+            .index
+            .iter()
+            .map(|spilled| (spilled.offset, spilled.len))
+            .collect();
+        entries.sort_unstable();
+        Ok(SpillSnapshot {
+            reader: BufReader::new(File::open(&self.path)?),
+            pos: 0,
+            entries: entries.into_iter(),
+            codec: self.codec,
+            _guard: self.snapshots.clone(),
+        })
+    }
+}
+
+/// Spilled urls as of [`Spill::snapshot`], read in file order. The segment is
+/// only appended to while this is alive, so the positions stay valid.
+pub(crate) struct SpillSnapshot<Ctx> {
+    reader: BufReader<File>,
+    pos: u64,
+    entries: std::vec::IntoIter<(u64, u32)>,
+    codec: Codec<Ctx>,
+    _guard: Arc<()>,
+}
+
+impl<Ctx> SpillSnapshot<Ctx> {
+    fn read(&mut self, offset: u64, len: u32) -> Result<NextUrl<Ctx>> {
+        self.reader.seek_relative((offset - self.pos) as i64)?;
+        let mut line = vec![0; len as usize];
+        self.reader.read_exact(&mut line)?;
+        self.pos = offset + len as u64;
+        self.codec.decode(serde_json::from_slice(&line)?)
+    }
+}
+
+impl<Ctx> Iterator for SpillSnapshot<Ctx> {
+    type Item = Result<NextUrl<Ctx>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, len) = self.entries.next()?;
+        let next = self.read(offset, len);
+        if next.is_err() {
+            // The reader is no longer where `pos` says it is.
+            self.entries = Vec::new().into_iter();
+        }
+        Some(next)
+    }
+}
+
+impl<Ctx> Drop for Spill<Ctx> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
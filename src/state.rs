@@ -0,0 +1,249 @@
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use reqwest::{
+    header::{HeaderName, HeaderValue},
+    Method, Url,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    dedup::DedupStore,
+    error::{Error, Result},
+    next_action::NextUrl,
+};
+
+const HEADER: &str = "syphon checkpoint 2";
+const FILE: &str = "checkpoint";
+
+/// Converts urls and their context to and from JSON, for everything that
+/// keeps urls on disk.
+pub(crate) struct Codec<Ctx> {
+    encode: fn(&Ctx) -> serde_json::Result<Value>,
+    decode: fn(Value) -> serde_json::Result<Ctx>,
+}
+
+impl<Ctx> Clone for Codec<Ctx> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Ctx> Copy for Codec<Ctx> {}
+
+#[cfg(feature = "serde")]
+impl<Ctx> Codec<Ctx>
+where
+    Ctx: serde::Serialize + serde::de::DeserializeOwned,
+{
+    pub(crate) fn new() -> Self {
+        Self {
+            encode: |data| serde_json::to_value(data),
+            decode: serde_json::from_value,
+        }
+    }
+}
+
+impl<Ctx> Codec<Ctx> {
+    pub(crate) fn encode(&self, next: &NextUrl<Ctx>) -> Result<Value> {
+        Ok(json!({
+            "url": next.url.as_str(),
+            "data": (self.encode)(&next.data)?,
+            "method": next.method.as_str(),
+            "headers": next
+                .headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes()))
+                .collect::<Vec<_>>(),
+            "body": next.body,
+            "timeout": next.timeout.map(|timeout| timeout.as_millis() as u64),
+            "callback": next.callback,
+            "priority": next.priority,
+            "attempt": next.attempt,
+            "depth": next.depth,
+            "external_hops": next.external_hops,
+        }))
+    }
+
+    pub(crate) fn decode(&self, mut value: Value) -> Result<NextUrl<Ctx>> {
+        let field = |name: &str| {
+            value
+                .get(name)
+                .and_then(Value::as_i64)
+                .ok_or_else(|| Error::InvalidState(format!("missing {}", name)))
+        };
+        let priority = field("priority")?;
+        let attempt = field("attempt")? as u32;
+        let depth = field("depth")? as usize;
+        let external_hops = field("external_hops")? as usize;
+        let url = value
+            .get("url")
+            .and_then(Value::as_str)
+            .and_then(|url| Url::parse(url).ok())
+            .ok_or_else(|| Error::InvalidState("missing url".into()))?;
+        let method = value
+            .get("method")
+            .and_then(Value::as_str)
+            .and_then(|method| Method::from_bytes(method.as_bytes()).ok())
+            .ok_or_else(|| Error::InvalidState("missing method".into()))?;
+        let headers: Vec<(String, Vec<u8>)> = serde_json::from_value(value["headers"].take())?;
+        let headers = headers
+            .into_iter()
+            .map(|(name, value)| {
+                let name = HeaderName::from_bytes(name.as_bytes()).ok()?;
+                Some((name, HeaderValue::from_bytes(&value).ok()?))
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| Error::InvalidState("invalid header".into()))?;
+        let body = serde_json::from_value(value["body"].take())?;
+        let timeout = value["timeout"].as_u64().map(Duration::from_millis);
+        let callback = value["callback"]
+            .as_str()
+            .map(|callback| Cow::Owned(callback.to_string()));
+        let data = (self.decode)(value["data"].take())?;
+        Ok(NextUrl {
+            method,
+            headers,
+            body,
+            timeout,
+            callback,
+            priority,
+            attempt,
+            depth,
+            external_hops,
+            ..NextUrl::new(url, data)
+        })
+    }
+}
+
+/// A directory holding the checkpoint of one website: the urls still to
+/// visit, including the ones that were in flight, and the dedup store.
+///
+/// The checkpoint is a single file, replaced atomically on every save: a
+/// header line, the length of the saved dedup store, the store itself, then
+/// one JSON object per pending url.
+pub(crate) struct StateDir<Ctx> {
+    dir: PathBuf,
+    codec: Codec<Ctx>,
+}
+
+impl<Ctx> StateDir<Ctx> {
+    pub(crate) fn new(dir: PathBuf, codec: Codec<Ctx>) -> Self {
+        Self { dir, codec }
+    }
+
+    /// Loads the seen fingerprints into `seen` and returns the pending urls,
+    /// or `None` if nothing was saved yet.
+    pub(crate) fn load(&self, seen: &dyn DedupStore) -> Result<Option<Vec<NextUrl<Ctx>>>> {
+        let file = match File::open(self.dir.join(FILE)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line.trim_end() != HEADER {
+            return Err(Error::InvalidState("unknown header".into()));
+        }
+        line.clear();
+        reader.read_line(&mut line)?;
+        let len: u64 = line
+            .trim_end()
+            .parse()
+            .map_err(|_| Error::InvalidState("invalid dedup store length".into()))?;
+        let mut store = (&mut reader).take(len);
+        seen.load(&mut store)?;
+        if store.limit() != 0 {
+            return Err(Error::InvalidState("dedup store was not fully read".into()));
+        }
+        let mut pending = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if !line.is_empty() {
+                pending.push(self.codec.decode(serde_json::from_str(&line)?)?);
+            }
+        }
+        Ok(Some(pending))
+    }
+
+    /// Saves `seen` before taking the `pending` snapshot, so that a url
+    /// discovered in between is at worst visited twice and never lost.
+    pub(crate) fn save<I>(
+        &self,
+        seen: &dyn DedupStore,
+        pending: impl FnOnce() -> I,
+    ) -> Result<usize>
+    where
+        I: IntoIterator<Item = NextUrl<Ctx>>,
+    {
+        let mut store = Vec::new();
+        seen.save(&mut store)?;
+        let pending = pending();
+
+        fs::create_dir_all(&self.dir)?;
+        let tmp = self.dir.join(format!("{}.tmp", FILE));
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "{}", store.len())?;
+        writer.write_all(&store)?;
+        let mut count = 0;
+        for next in pending {
+            serde_json::to_writer(&mut writer, &self.codec.encode(&next)?)?;
+            writer.write_all(b"\n")?;
+            count += 1;
+        }
+        writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?
+            .sync_all()?;
+        fs::rename(tmp, self.dir.join(FILE))?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{dedup::ExactSet, fingerprint::Fingerprint};
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("syphon-state-{}", uuid::Uuid::new_v4()));
+        let state = StateDir::<(String, u32)>::new(dir.clone(), Codec::new());
+        assert!(state.load(&ExactSet::default()).unwrap().is_none());
+
+        let next = NextUrl {
+            depth: 3,
+            attempt: 1,
+            ..NextUrl::new(
+                Url::parse("https://example.com/a?b=1").unwrap(),
+                ("ctx".to_string(), 7),
+            )
+            .priority(-2)
+            .method(Method::POST)
+            .header("accept", HeaderValue::from_static("text/html"))
+            .form(&[("q", "rust")])
+            .unwrap()
+            .timeout(Duration::from_secs(90))
+            .callback("parse_product")
+        };
+        let seen = ExactSet::default();
+        seen.insert(Fingerprint(1));
+        seen.insert(Fingerprint(u128::MAX));
+        state.save(&seen, || vec![next.clone()]).unwrap();
+
+        let loaded = ExactSet::default();
+        let pending = state.load(&loaded).unwrap().unwrap();
+        assert!(!loaded.insert(Fingerprint(1)));
+        assert!(!loaded.insert(Fingerprint(u128::MAX)));
+        assert!(loaded.insert(Fingerprint(2)));
+        assert_eq!(pending, [next]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,83 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use crate::{budget::StopReason, dedup::DedupStore};
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub(crate) fetched: AtomicU64,
+    pub(crate) bytes: AtomicU64,
+    pub(crate) outputs: AtomicU64,
+    pub(crate) retried: AtomicU64,
+    pub(crate) failed: AtomicU64,
+    pub(crate) host_paused: AtomicU64,
+    pub(crate) requeued: AtomicU64,
+    pub(crate) robots_denied: AtomicU64,
+    pub(crate) out_of_scope: AtomicU64,
+    pub(crate) too_deep: AtomicU64,
+    pub(crate) rejected: Mutex<BTreeMap<&'static str, u64>>,
+    pub(crate) stop_reason: Mutex<Option<StopReason>>,
+    pub(crate) dedup: OnceLock<Arc<dyn DedupStore>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsSnapshot {
+    pub fetched: u64,
+    pub bytes: u64,
+    pub outputs: u64,
+    pub retried: u64,
+    pub failed: u64,
+    pub host_paused: u64,
+    pub requeued: u64,
+    pub robots_denied: u64,
+    pub out_of_scope: u64,
+    pub too_deep: u64,
+    /// How many times each handler was skipped because an extractor failed.
+    pub rejected: BTreeMap<&'static str, u64>,
+    /// Set once the website has finished.
+    pub stop_reason: Option<StopReason>,
+    /// Estimated bytes used to remember seen requests.
+    pub dedup_memory: u64,
+    /// Estimated chance that a new url is wrongly skipped as already seen.
+    pub dedup_false_positive_rate: f64,
+}
+
+impl Stats {
+    pub(crate) fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add(counter: &AtomicU64, amount: u64) {
+        counter.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            fetched: self.fetched.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            outputs: self.outputs.load(Ordering::Relaxed),
+            retried: self.retried.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+            host_paused: self.host_paused.load(Ordering::Relaxed),
+            requeued: self.requeued.load(Ordering::Relaxed),
+            robots_denied: self.robots_denied.load(Ordering::Relaxed),
+            out_of_scope: self.out_of_scope.load(Ordering::Relaxed),
+            too_deep: self.too_deep.load(Ordering::Relaxed),
+            rejected: self.rejected.lock().unwrap().clone(),
+            stop_reason: *self.stop_reason.lock().unwrap(),
+            dedup_memory: self
+                .dedup
+                .get()
+                .map_or(0, |dedup| dedup.memory_usage() as u64),
+            dedup_false_positive_rate: self
+                .dedup
+                .get()
+                .map_or(0.0, |dedup| dedup.false_positive_rate()),
+        }
+    }
+}
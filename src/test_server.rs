@@ -0,0 +1,202 @@
+//! A minimal HTTP/1.1 server for tests that crawl over the network.
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use reqwest::Url;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::next_action::WebsiteOutput;
+
+/// A request as the server received it. Header names are lowercase.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub(crate) method: String,
+    pub(crate) path: String,
+    pub(crate) headers: HashMap<String, String>,
+}
+
+pub(crate) struct Reply {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+    delay: Duration,
+}
+
+impl Reply {
+    pub(crate) fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: String::new(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn html(body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            ..Self::status(200).header("content-type", "text/html")
+        }
+    }
+
+    /// A page linking to every path of `links`.
+    pub(crate) fn links<I, S>(links: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Display,
+    {
+        Self::html(
+            links
+                .into_iter()
+                .map(|link| format!(r#"<a href="{}">{}</a>"#, link, link))
+                .collect::<String>(),
+        )
+    }
+
+    pub(crate) fn redirect(status: u16, location: impl Into<String>) -> Self {
+        Self::status(status).header("location", location)
+    }
+
+    pub(crate) fn header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Waits before answering.
+    pub(crate) fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+pub(crate) struct TestServer {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl TestServer {
+    pub(crate) async fn start<F>(route: F) -> Self
+    where
+        F: Fn(&Request) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let route = Arc::new(route);
+        let log = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let route = route.clone();
+                let log = log.clone();
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    let Some(request) = read_request(&mut stream).await else {
+                        return;
+                    };
+                    log.lock().unwrap().push(request.clone());
+                    let reply = route(&request);
+                    tokio::time::sleep(reply.delay).await;
+                    let _ = write_reply(stream.get_mut(), reply).await;
+                });
+            }
+        });
+        Self { addr, requests }
+    }
+
+    pub(crate) fn url(&self, path: &str) -> Url {
+        self.url_on("127.0.0.1", path)
+    }
+
+    /// A url on this server under another host name.
+    pub(crate) fn url_on(&self, host: &str, path: &str) -> Url {
+        Url::parse(&format!("http://{}:{}{}", host, self.addr.port(), path)).unwrap()
+    }
+
+    pub(crate) fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn read_request(stream: &mut BufReader<TcpStream>) -> Option<Request> {
+    let mut line = String::new();
+    stream.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let path = parts.next()?.to_string();
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await.ok()?;
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+    }
+    let len = headers
+        .get("content-length")
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body).await.ok()?;
+    Some(Request {
+        method,
+        path,
+        headers,
+    })
+}
+
+async fn write_reply(stream: &mut TcpStream, reply: Reply) -> std::io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} Test\r\ncontent-length: {}\r\nconnection: close\r\n",
+        reply.status,
+        reply.body.len()
+    );
+    for (name, value) in reply.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(reply.body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// What the test crawls emit: the path of a fetched page, or a note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Out(pub(crate) String);
+
+impl WebsiteOutput for Out {
+    fn should_process(&self) -> bool {
+        true
+    }
+}
+
+/// Emits the path of every page and visits every link on it.
+#[cfg(feature = "extractor")]
+pub(crate) async fn follow(
+    crate::extractor::Url(url): crate::extractor::Url,
+    crate::extractor::Html(dom): crate::extractor::Html,
+) -> Vec<crate::next_action::NextAction<(), Out>> {
+    use crate::next_action::{NextAction, NextUrl};
+
+    let selector = scraper::Selector::parse("a[href]").unwrap();
+    let links: Vec<_> = dom
+        .lock()
+        .unwrap()
+        .select(&selector)
+        .filter_map(|a| url.join(a.value().attr("href")?).ok())
+        .collect();
+    links
+        .into_iter()
+        .map(|link| NextAction::Visit(NextUrl::new(link, ())))
+        .chain([NextAction::PipeOutput(Out(url.path().to_string()))])
+        .collect()
+}
@@ -0,0 +1,197 @@
+use std::{sync::Mutex, time::Duration};
+
+use hashbrown::HashMap;
+
+/// Adjusts per-host delay and concurrency from observed latency and errors.
+///
+/// The delay converges to `latency / target_concurrency`, so that on average
+/// `target_concurrency` requests are in flight per host. Concurrency grows by
+/// one slot per round of successful responses and halves on errors.
+#[derive(Debug, Clone)]
+pub struct AutoThrottle {
+    target_concurrency: f64,
+    start_delay: Duration,
+    min_delay: Duration,
+    max_delay: Duration,
+    max_concurrency: usize,
+}
+
+impl Default for AutoThrottle {
+    fn default() -> Self {
+        Self {
+            target_concurrency: 1.0,
+            start_delay: Duration::from_secs(1),
+            min_delay: Duration::ZERO,
+            max_delay: Duration::from_secs(60),
+            max_concurrency: 8,
+        }
+    }
+}
+
+impl AutoThrottle {
+    pub fn target_concurrency(mut self, target: f64) -> Self {
+        self.target_concurrency = target.max(f64::EPSILON);
+        self
+    }
+
+    pub fn start_delay(mut self, delay: Duration) -> Self {
+        self.start_delay = delay;
+        self
+    }
+
+    pub fn min_delay(mut self, delay: Duration) -> Self {
+        self.min_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    pub fn max_concurrency(mut self, limit: usize) -> Self {
+        self.max_concurrency = limit.max(1);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Success,
+    /// A response that should not make the throttle more aggressive, e.g. 404.
+    Neutral,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HostThrottle {
+    pub delay: Duration,
+    pub concurrency: usize,
+    pub latency: Option<Duration>,
+    pub error_rate: f64,
+}
+
+struct HostState {
+    delay: Duration,
+    concurrency: f64,
+    latency: Option<Duration>,
+    error_rate: f64,
+}
+
+impl HostState {
+    fn current(&self) -> HostThrottle {
+        HostThrottle {
+            delay: self.delay,
+            concurrency: self.concurrency as usize,
+            latency: self.latency,
+            error_rate: self.error_rate,
+        }
+    }
+}
+
+pub struct Throttle {
+    config: AutoThrottle,
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl Throttle {
+    pub(crate) fn new(config: AutoThrottle) -> Self {
+        Self {
+            config,
+            hosts: Default::default(),
+        }
+    }
+
+    /// Delay and concurrency for a host that has not been observed yet.
+    pub(crate) fn initial(&self) -> (Duration, usize) {
+        let delay = self
+            .config
+            .start_delay
+            .clamp(self.config.min_delay, self.config.max_delay);
+        (delay, 1)
+    }
+
+    pub(crate) fn record(
+        &self,
+        host: &str,
+        latency: Option<Duration>,
+        outcome: Outcome,
+    ) -> HostThrottle {
+        let config = &self.config;
+        let mut hosts = self.hosts.lock().unwrap();
+        let state = hosts.entry_ref(host).or_insert_with(|| {
+            let (delay, concurrency) = self.initial();
+            HostState {
+                delay,
+                concurrency: concurrency as f64,
+                latency: None,
+                error_rate: 0.0,
+            }
+        });
+
+        let error = if outcome == Outcome::Error { 1.0 } else { 0.0 };
+        state.error_rate = state.error_rate * 0.9 + error * 0.1;
+
+        if let Some(sample) = latency {
+            let smoothed = match state.latency {
+                Some(prev) => prev.mul_f64(0.7) + sample.mul_f64(0.3),
+                None => sample,
+            };
+            state.latency = Some(smoothed);
+            let target = smoothed.div_f64(config.target_concurrency);
+            let mut delay = (state.delay + target) / 2;
+            if outcome != Outcome::Success {
+                delay = delay.max(state.delay);
+            }
+            state.delay = delay.clamp(config.min_delay, config.max_delay);
+        }
+
+        let max = config.max_concurrency as f64;
+        state.concurrency = match outcome {
+            Outcome::Success => (state.concurrency + 1.0 / state.concurrency).min(max),
+            Outcome::Neutral => state.concurrency,
+            Outcome::Error => (state.concurrency / 2.0).max(1.0),
+        };
+
+        state.current()
+    }
+
+    pub fn host(&self, host: &str) -> Option<HostThrottle> {
+        self.hosts.lock().unwrap().get(host).map(HostState::current)
+    }
+
+    pub fn snapshot(&self) -> HashMap<String, HostThrottle> {
+        self.hosts
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(host, state)| (host.clone(), state.current()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_throttle_converges_to_target() {
+        let throttle = Throttle::new(
+            AutoThrottle::default()
+                .target_concurrency(2.0)
+                .max_concurrency(4),
+        );
+        let latency = Some(Duration::from_millis(200));
+        for _ in 0..32 {
+            throttle.record("a", latency, Outcome::Success);
+        }
+        let current = throttle.host("a").unwrap();
+        assert!(current.delay.abs_diff(Duration::from_millis(100)) < Duration::from_millis(5));
+        assert_eq!(current.concurrency, 4);
+
+        let current = throttle.record("a", latency, Outcome::Error);
+        assert_eq!(current.concurrency, 2);
+        assert!(current.delay >= Duration::from_millis(100));
+        assert!(current.error_rate > 0.0);
+    }
+}
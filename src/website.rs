@@ -1,27 +1,109 @@
-use std::{fmt::Debug, marker::PhantomData, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    fmt::Debug,
+    future::Future,
+    marker::PhantomData,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use futures::future::join_all;
-use hashbrown::HashSet;
 use log::{debug, error, info, warn};
-use reqwest::{Method, Request, Url};
+use reqwest::{
+    header::{
+        HeaderMap, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION,
+        PROXY_AUTHORIZATION, RETRY_AFTER,
+    },
+    redirect, Method, Request, StatusCode, Url,
+};
 use tokio::{
-    sync::{mpsc, Mutex, Semaphore},
-    task::JoinHandle,
+    sync::{mpsc, watch, RwLock, Semaphore},
+    task::{JoinHandle, JoinSet},
 };
 
 use crate::{
-    handler::{Handler, HandlerBox, HandlerWrapper},
-    next_action::{NextAction, NextActionVector, NextUrl},
-    response::Response,
+    budget::{Budget, Limits, StopReason, Tracker},
+    control::{CrawlHandle, CrawlState, Phase},
+    dedup::{DedupStore, ExactSet},
+    error::Error,
+    fingerprint::{default_fingerprint, Canonicalize, Fingerprint, FingerprintFn},
+    frontier::{host_key, Frontier},
+    handler::{ErrorHandlerBox, FetchError, HandlerBox, HandlerWrapper, Named},
+    next_action::{IntoNextActionVec, NextAction, NextActionVector, NextUrl, WebsiteOutput},
+    order::CrawlOrder,
+    response::{AppState, Redirect, Rejection, Response},
+    retry::{parse_retry_after, Failure, RetryPolicy},
+    robots::{RobotsCache, RobotsFallback},
+    route::{Pattern, Route},
+    scope::Scope,
+    spill::Spill,
+    state::{Codec, StateDir},
+    stats::Stats,
+    throttle::{AutoThrottle, Outcome, Throttle},
 };
 
+struct Config {
+    starting_urls: Vec<Url>,
+    parallel_limit: usize,
+    max_depth: Option<usize>,
+    order: CrawlOrder,
+    budget: Budget,
+    host_parallel_limit: usize,
+    host_delay: Duration,
+    auto_throttle: Option<AutoThrottle>,
+    retry: RetryPolicy,
+    user_agent: String,
+    respect_robots: bool,
+    robots_fallback: RobotsFallback,
+    scope: Scope,
+    canonicalize: Canonicalize,
+    fingerprint: FingerprintFn,
+    dedup: Arc<dyn DedupStore>,
+    state_dir: Option<PathBuf>,
+    checkpoint_interval: Duration,
+    spill: Option<(PathBuf, usize)>,
+    callbacks: HashSet<Cow<'static, str>>,
+    app_state: Arc<AppState>,
+    on_rejection: Option<Arc<dyn Fn(&Rejection) + Send + Sync>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            starting_urls: Default::default(),
+            parallel_limit: 16,
+            max_depth: None,
+            order: Default::default(),
+            budget: Default::default(),
+            host_parallel_limit: usize::MAX,
+            host_delay: Duration::ZERO,
+            auto_throttle: None,
+            retry: Default::default(),
+            user_agent: concat!("syphon/", env!("CARGO_PKG_VERSION")).to_string(),
+            respect_robots: true,
+            robots_fallback: Default::default(),
+            scope: Default::default(),
+            canonicalize: Default::default(),
+            fingerprint: Arc::new(default_fingerprint),
+            dedup: Arc::new(ExactSet::default()),
+            state_dir: None,
+            checkpoint_interval: Duration::from_secs(60),
+            spill: None,
+            callbacks: Default::default(),
+            app_state: Default::default(),
+            on_rejection: None,
+        }
+    }
+}
+
 pub struct WebsiteBuilder<Ctx, Out, Handler>
 where
     Handler: HandlerWrapper<Ctx, Out>,
 {
-    starting_urls: Vec<Url>,
-    parallel_limit: usize,
+    config: Config,
     handler: Handler,
+    codec: Option<Codec<Ctx>>,
     _maker: PhantomData<fn() -> (Ctx, Out)>,
 }
 
@@ -32,12 +114,177 @@ where
     Handle: HandlerWrapper<Ctx, Out> + Send + Sync,
 {
     pub fn parallel_limit(mut self, limit: usize) -> Self {
-        self.parallel_limit = limit;
+        self.config.parallel_limit = limit;
+        self
+    }
+
+    /// Drops urls more than `depth` links away from a starting url.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.config.max_depth = Some(depth);
+        self
+    }
+
+    /// Which queued url to visit next. Defaults to
+    /// [`CrawlOrder::BreadthFirst`].
+    pub fn order(mut self, order: CrawlOrder) -> Self {
+        self.config.order = order;
+        self
+    }
+
+    /// Stops the website once it has sent `pages` requests, retries included.
+    pub fn max_pages(mut self, pages: u64) -> Self {
+        self.config.budget.pages = Some(pages);
+        self
+    }
+
+    /// Stops the website once it has emitted `outputs` outputs. Outputs past
+    /// the limit are dropped.
+    pub fn max_outputs(mut self, outputs: u64) -> Self {
+        self.config.budget.outputs = Some(outputs);
+        self
+    }
+
+    /// Stops the website once it has downloaded `bytes` of response bodies.
+    /// Requests already in flight still complete, so the total can go over.
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.config.budget.bytes = Some(bytes);
+        self
+    }
+
+    /// Stops the website once `duration` has passed since the crawl started.
+    pub fn max_duration(mut self, duration: Duration) -> Self {
+        self.config.budget.duration = Some(duration);
+        self
+    }
+
+    /// Caps the number of in-flight requests to any single host, on top of
+    /// `parallel_limit`.
+    pub fn host_parallel_limit(mut self, limit: usize) -> Self {
+        self.config.host_parallel_limit = limit;
+        self
+    }
+
+    /// Minimum delay between two requests to the same host.
+    pub fn host_delay(mut self, delay: Duration) -> Self {
+        self.config.host_delay = delay;
+        self
+    }
+
+    /// Lets per-host delay and concurrency follow observed latency and
+    /// errors. `parallel_limit`, `host_parallel_limit` and `host_delay` still
+    /// bound the adjusted values.
+    pub fn auto_throttle(mut self, throttle: AutoThrottle) -> Self {
+        self.config.auto_throttle = Some(throttle);
+        self
+    }
+
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.config.retry = policy;
+        self
+    }
+
+    /// Sent with every request and used to pick the `robots.txt` rules.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.config.user_agent = user_agent.into();
+        self
+    }
+
+    /// Fetch `/robots.txt` for each host and skip disallowed urls. On by default.
+    pub fn respect_robots(mut self, respect: bool) -> Self {
+        self.config.respect_robots = respect;
+        self
+    }
+
+    pub fn robots_fallback(mut self, fallback: RobotsFallback) -> Self {
+        self.config.robots_fallback = fallback;
+        self
+    }
+
+    /// Which discovered urls to follow. Defaults to [`Scope::same_host`].
+    pub fn scope(mut self, scope: Scope) -> Self {
+        self.config.scope = scope;
+        self
+    }
+
+    /// How urls are normalized before being fingerprinted for dedup.
+    pub fn canonicalize(mut self, canonicalize: Canonicalize) -> Self {
+        self.config.canonicalize = canonicalize;
+        self
+    }
+
+    /// Replaces the dedup key, computed from the method, the canonical url,
+    /// the request headers and the body. Requests with equal fingerprints
+    /// are fetched once.
+    pub fn fingerprint<F>(mut self, fingerprint: F) -> Self
+    where
+        F: Fn(&Method, &Url, &HeaderMap, Option<&[u8]>) -> Fingerprint + Send + Sync + 'static,
+    {
+        self.config.fingerprint = Arc::new(fingerprint);
+        self
+    }
+
+    /// Where fingerprints of seen requests are kept. Defaults to an
+    /// [`ExactSet`]; [`crate::dedup::ScalableBloom`] and
+    /// [`crate::dedup::Hybrid`] bound memory at the cost of false positives.
+    pub fn dedup(mut self, store: impl DedupStore + 'static) -> Self {
+        self.config.dedup = Arc::new(store);
+        self
+    }
+
+    /// Keeps the frontier and the seen urls in `dir`, checkpointed
+    /// periodically and when the website finishes, except after
+    /// [`CrawlHandle::stop`] which leaves the last checkpoint in place. If
+    /// `dir` already holds a checkpoint the crawl resumes from it: seen urls
+    /// are skipped and urls that were queued or in flight are visited again.
+    #[cfg(feature = "serde")]
+    pub fn persist(mut self, dir: impl Into<PathBuf>) -> Self
+    where
+        Ctx: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.config.state_dir = Some(dir.into());
+        self.codec = Some(Codec::new());
+        self
+    }
+
+    /// How often `persist` writes a checkpoint. Defaults to a minute.
+    pub fn checkpoint_interval(mut self, interval: Duration) -> Self {
+        self.config.checkpoint_interval = interval;
+        self
+    }
+
+    /// Keeps at most `in_memory` queued urls in memory and appends the rest
+    /// to a segment file in `dir`, reading them back in order as the queue
+    /// drains.
+    #[cfg(feature = "serde")]
+    pub fn spill_frontier(mut self, dir: impl Into<PathBuf>, in_memory: usize) -> Self
+    where
+        Ctx: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        self.config.spill = Some((dir.into(), in_memory));
+        self.codec = Some(Codec::new());
+        self
+    }
+
+    /// Makes `state` available to every handler through
+    /// [`crate::extractor::State`]. Holds one value per type, a later call
+    /// with the same type replaces the earlier one.
+    pub fn with_state<T: Clone + Send + Sync + 'static>(mut self, state: T) -> Self {
+        Arc::make_mut(&mut self.config.app_state).insert(state);
+        self
+    }
+
+    /// Called every time a handler is skipped because one of its
+    /// extractors failed.
+    pub fn on_rejection<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Rejection) + Send + Sync + 'static,
+    {
+        self.config.on_rejection = Some(Arc::new(f));
         self
     }
 
     pub fn start_with(mut self, url: Url) -> Self {
-        self.starting_urls.push(url);
+        self.config.starting_urls.push(url);
         self
     }
 
@@ -48,9 +295,57 @@ where
     {
         let wrapper = HandlerBox::from_handler(handler);
         WebsiteBuilder {
-            starting_urls: self.starting_urls,
-            parallel_limit: self.parallel_limit,
+            config: self.config,
             handler: self.handler.pair(wrapper),
+            codec: self.codec,
+            _maker: Default::default(),
+        }
+    }
+
+    /// Adds a handler that only runs for urls matching `pattern`.
+    pub fn route<T, H>(
+        self,
+        pattern: impl Into<Pattern>,
+        handler: H,
+    ) -> WebsiteBuilder<Ctx, Out, impl HandlerWrapper<Ctx, Out>>
+    where
+        T: 'static,
+        H: crate::handler::Handler<T, Ctx, Out> + Send + Sync + 'static,
+        Ctx: Send + 'static,
+        Out: Send + 'static,
+    {
+        self.and(Route::new(pattern, handler))
+    }
+
+    /// Adds a handler that only runs for urls queued with
+    /// [`NextUrl::callback`] set to `name`.
+    pub fn named<T, H>(
+        mut self,
+        name: impl Into<Cow<'static, str>>,
+        handler: H,
+    ) -> WebsiteBuilder<Ctx, Out, impl HandlerWrapper<Ctx, Out>>
+    where
+        T: 'static,
+        H: crate::handler::Handler<T, Ctx, Out> + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.config.callbacks.insert(name.clone());
+        self.and(Named::new(name, handler))
+    }
+
+    /// Called with every non-2xx response and every request that failed for
+    /// good. Urls it returns are visited like any other.
+    pub fn on_error<F, Fut>(self, f: F) -> WebsiteBuilder<Ctx, Out, impl HandlerWrapper<Ctx, Out>>
+    where
+        F: Fn(FetchError, Ctx) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoNextActionVec<Ctx, Out>,
+        Out: WebsiteOutput,
+    {
+        WebsiteBuilder {
+            config: self.config,
+            handler: self.handler.pair(ErrorHandlerBox::from_fn(f)),
+            codec: self.codec,
             _maker: Default::default(),
         }
     }
@@ -61,12 +356,18 @@ where
     Handler: HandlerWrapper<Ctx, Out>,
 {
     fn from(val: WebsiteBuilder<Ctx, Out, Handler>) -> Self {
+        let throttle = val.config.auto_throttle.clone().map(Throttle::new);
+        let stats = Stats::default();
+        let _ = stats.dedup.set(val.config.dedup.clone());
         Website {
-            starting_urls: Arc::new(val.starting_urls),
-            parallel_limit: val.parallel_limit,
+            config: Arc::new(val.config),
             handler: Arc::from(val.handler),
+            stats: Arc::new(stats),
+            throttle: throttle.map(Arc::new),
+            codec: val.codec,
             join_handler: None,
-            sender: None,
+            shared: None,
+            launch: None,
             _maker: Default::default(),
         }
     }
@@ -76,14 +377,31 @@ pub struct Website<Ctx, Out, Handler>
 where
     Handler: HandlerWrapper<Ctx, Out>,
 {
-    starting_urls: Arc<Vec<Url>>,
-    parallel_limit: usize,
+    config: Arc<Config>,
     handler: Arc<Handler>,
+    stats: Arc<Stats>,
+    throttle: Option<Arc<Throttle>>,
+    codec: Option<Codec<Ctx>>,
     join_handler: Option<JoinHandle<()>>,
-    sender: Option<mpsc::Sender<NextUrl<Ctx>>>,
+    shared: Option<Arc<Shared<Ctx, Handler>>>,
+    launch: Option<Launch<Out>>,
     _maker: PhantomData<fn() -> Out>,
 }
 
+impl<Ctx, Out, Handler> Website<Ctx, Out, Handler>
+where
+    Handler: HandlerWrapper<Ctx, Out>,
+{
+    pub fn stats(&self) -> Arc<Stats> {
+        self.stats.clone()
+    }
+
+    /// Current per-host values picked by the auto-throttle, if enabled.
+    pub fn throttle(&self) -> Option<Arc<Throttle>> {
+        self.throttle.clone()
+    }
+}
+
 impl<Ctx, Out, T, Handler> Website<Ctx, Out, HandlerBox<Handler, T, Ctx, Out>>
 where
     Handler: crate::handler::Handler<T, Ctx, Out>,
@@ -91,9 +409,9 @@ where
 {
     pub fn handle(handler: Handler) -> WebsiteBuilder<Ctx, Out, HandlerBox<Handler, T, Ctx, Out>> {
         WebsiteBuilder {
-            starting_urls: Default::default(),
-            parallel_limit: 16,
+            config: Default::default(),
             handler: HandlerBox::from_handler(handler),
+            codec: None,
             _maker: Default::default(),
         }
     }
@@ -105,9 +423,9 @@ where
     T2: WebsiteWrapper<Out>;
 
 pub trait WebsiteWrapper<Output> {
-    fn init(&mut self, output_sender: mpsc::Sender<Output>);
+    fn init(&mut self, output_sender: mpsc::Sender<Output>, handle: &CrawlHandle);
 
-    fn launch(&self);
+    fn launch(&mut self);
 
     fn pair<T: WebsiteWrapper<Output>>(self, other: T) -> WebsitePair<T, Self, Output>
     where
@@ -122,12 +440,12 @@ where
     T1: WebsiteWrapper<Output>,
     T2: WebsiteWrapper<Output>,
 {
-    fn init(&mut self, output_sender: mpsc::Sender<Output>) {
-        self.0.init(output_sender.clone());
-        self.1.init(output_sender)
+    fn init(&mut self, output_sender: mpsc::Sender<Output>, handle: &CrawlHandle) {
+        self.0.init(output_sender.clone(), handle);
+        self.1.init(output_sender, handle)
     }
 
-    fn launch(&self) {
+    fn launch(&mut self) {
         self.0.launch();
         self.1.launch()
     }
@@ -139,127 +457,907 @@ where
     Output: Send + 'static + Debug,
     Handler: HandlerWrapper<Ctx, Output> + Send + Sync + 'static,
 {
-    fn init(&mut self, output_sender: mpsc::Sender<Output>) {
-        let (cx, rx) = mpsc::channel(self.parallel_limit * 4);
-        let handlers = self.handler.clone();
-        let parallel = self.parallel_limit;
-        self.sender = Some(cx.clone());
-        self.join_handler = Some(tokio::spawn(async move {
-            _fetcher(
-                parallel,
-                cx,
-                rx,
-                handlers,
-                output_sender,
-                Default::default(),
-            )
-            .await
-        }))
+    fn init(&mut self, output_sender: mpsc::Sender<Output>, handle: &CrawlHandle) {
+        let mut frontier = Frontier::new(self.config.host_delay, self.config.host_parallel_limit)
+            .with_order(self.config.order.clone());
+        if self.config.respect_robots {
+            frontier = frontier.with_robots();
+        }
+        if let Some(throttle) = self.throttle.as_ref() {
+            let (delay, limit) = throttle.initial();
+            frontier = frontier.with_host_defaults(delay, limit);
+        }
+        let state = self
+            .config
+            .state_dir
+            .clone()
+            .zip(self.codec)
+            .map(|(dir, codec)| Arc::new(StateDir::new(dir, codec)));
+        if state.is_some() {
+            frontier = frontier.with_tracking();
+        }
+        if let (Some((dir, in_memory)), Some(codec)) = (self.config.spill.as_ref(), self.codec) {
+            match Spill::new(dir, *in_memory, codec) {
+                Ok(spill) => frontier = frontier.with_spill(spill),
+                Err(err) => error!(
+                    "unable to create frontier segment, keeping it in memory: {}",
+                    err
+                ),
+            }
+        }
+        let shared = Arc::new(Shared {
+            config: self.config.clone(),
+            handlers: self.handler.clone(),
+            stats: self.stats.clone(),
+            throttle: self.throttle.clone(),
+            robots: self.config.respect_robots.then(|| {
+                RobotsCache::new(self.config.user_agent.clone(), self.config.robots_fallback)
+            }),
+            client: reqwest::Client::builder()
+                .user_agent(&self.config.user_agent)
+                .redirect(redirect::Policy::none())
+                .build()
+                .unwrap(),
+            frontier: Arc::new(frontier),
+            scope: self
+                .config
+                .scope
+                .clone()
+                .with_seeds(&self.config.starting_urls),
+            state,
+            checkpoint_lock: Default::default(),
+            limits: Limits {
+                website: Tracker::new(self.config.budget),
+                crawl: handle.budget(),
+            },
+        });
+        if let Some(state) = shared.state.as_ref() {
+            match state.load(&*self.config.dedup) {
+                Ok(Some(pending)) => {
+                    info!(
+                        "resuming from checkpoint with {} pending urls",
+                        pending.len()
+                    );
+                    for next in pending {
+                        shared.frontier.push(next);
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => error!("unable to load checkpoint, starting over: {}", err),
+            }
+        }
+        for ele in self.config.starting_urls.iter() {
+            let next = NextUrl::new(ele.clone(), Default::default());
+            if self.config.dedup.insert(shared.fingerprint(&next)) {
+                shared.frontier.push(next);
+            }
+        }
+        let (commands, phase) = handle.register();
+        self.shared = Some(shared);
+        self.launch = Some(Launch {
+            output_sender,
+            handle: handle.clone(),
+            commands,
+            phase,
+        });
     }
 
-    fn launch(&self) {
-        let Some(sender) = self.sender.clone() else {
+    fn launch(&mut self) {
+        let (Some(shared), Some(launch)) = (self.shared.clone(), self.launch.take()) else {
             return;
         };
-        let starting_urls = self.starting_urls.clone();
-        tokio::spawn(async move {
-            for ele in starting_urls.iter() {
-                let _ = sender
-                    .send(NextUrl {
-                        url: ele.clone(),
-                        data: Default::default(),
-                    })
-                    .await;
+        self.join_handler = Some(tokio::spawn(async move { _fetcher(shared, launch).await }))
+    }
+}
+
+struct Launch<Out> {
+    output_sender: mpsc::Sender<Out>,
+    // Keeps `commands` open even if the user drops every handle.
+    handle: CrawlHandle,
+    commands: watch::Receiver<CrawlState>,
+    phase: watch::Sender<Phase>,
+}
+
+struct Shared<Ctx, Handler> {
+    config: Arc<Config>,
+    handlers: Arc<Handler>,
+    stats: Arc<Stats>,
+    throttle: Option<Arc<Throttle>>,
+    robots: Option<RobotsCache>,
+    client: reqwest::Client,
+    frontier: Arc<Frontier<Ctx>>,
+    scope: Scope,
+    state: Option<Arc<StateDir<Ctx>>>,
+    /// Held for reading between a dedup insert and the matching frontier
+    /// push, and for writing while a checkpoint snapshots both, so that a
+    /// checkpoint never sees a url as seen without it being pending. Async
+    /// so that waiting on it never blocks a runtime thread.
+    checkpoint_lock: Arc<RwLock<()>>,
+    limits: Limits,
+}
+
+impl<Ctx, Handler> Shared<Ctx, Handler> {
+    fn reject(&self, rejection: Rejection) {
+        warn!("{}", rejection);
+        *self
+            .stats
+            .rejected
+            .lock()
+            .unwrap()
+            .entry(rejection.handler)
+            .or_default() += 1;
+        if let Some(on_rejection) = &self.config.on_rejection {
+            on_rejection(&rejection);
+        }
+    }
+
+    fn fingerprint(&self, next: &NextUrl<Ctx>) -> Fingerprint {
+        let url = self.config.canonicalize.apply(&next.url);
+        (self.config.fingerprint)(&next.method, &url, &next.headers, next.body.as_deref())
+    }
+
+    async fn robots_allow(&self, url: &Url) -> bool {
+        let Some(robots) = self.robots.as_ref() else {
+            return true;
+        };
+        let robots = robots.get(url).await;
+        self.frontier
+            .robots_loaded(&host_key(url), robots.crawl_delay());
+        robots.is_allowed(url)
+    }
+
+    /// Whether `url` is disallowed by rules already loaded for its host.
+    fn robots_deny(&self, url: &Url) -> bool {
+        self.robots
+            .as_ref()
+            .and_then(|robots| robots.cached(url))
+            .is_some_and(|robots| !robots.is_allowed(url))
+    }
+
+    async fn checkpoint(&self)
+    where
+        Ctx: Clone + Send + 'static,
+    {
+        let Some(state) = self.state.clone() else {
+            return;
+        };
+        let dedup = self.config.dedup.clone();
+        let frontier = self.frontier.clone();
+        let guard = self.checkpoint_lock.clone().write_owned().await;
+        // Released once both snapshots are taken, before anything is written.
+        let save = move || {
+            state.save(&*dedup, move || {
+                let pending = frontier.snapshot();
+                drop(guard);
+                pending
+            })
+        };
+        match tokio::task::spawn_blocking(save).await {
+            Ok(Ok(count)) => debug!("checkpointed {} pending urls", count),
+            Ok(Err(err)) => error!("unable to write checkpoint: {}", err),
+            Err(err) => error!("unable to write checkpoint: {}", err),
+        }
+    }
+
+    fn observe(&self, url: &Url, latency: Option<Duration>, outcome: Outcome) {
+        let Some(throttle) = self.throttle.as_ref() else {
+            return;
+        };
+        let host = host_key(url);
+        let current = throttle.record(&host, latency, outcome);
+        self.frontier
+            .set_host_limits(&host, current.delay, current.concurrency);
+    }
+}
+
+const MAX_REDIRECTS: usize = 10;
+
+/// Sends `next`, following redirects by hand so that every hop is recorded.
+async fn _send<Ctx, Handler>(
+    next: &NextUrl<Ctx>,
+    shared: &Shared<Ctx, Handler>,
+) -> reqwest::Result<(reqwest::Response, Vec<Redirect>)> {
+    let mut method = next.method.clone();
+    let mut headers = next.headers.clone();
+    let mut body = next.body.clone();
+    let mut url = next.url.clone();
+    let mut redirects = Vec::new();
+    loop {
+        let mut request = Request::new(method.clone(), url.clone());
+        *request.headers_mut() = headers.clone();
+        *request.body_mut() = body.clone().map(Into::into);
+        *request.timeout_mut() = next.timeout;
+        let resp = shared.client.execute(request).await?;
+
+        let status = resp.status();
+        let location = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok());
+        let Some(location) = location.filter(|_| status.is_redirection()) else {
+            return Ok((resp, redirects));
+        };
+        if redirects.len() == MAX_REDIRECTS {
+            warn!("{} redirected too many times, stopping", next.url);
+            return Ok((resp, redirects));
+        }
+        if status == StatusCode::SEE_OTHER
+            || (method == Method::POST
+                && matches!(status, StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND))
+        {
+            method = Method::GET;
+            body = None;
+            headers.remove(CONTENT_TYPE);
+            headers.remove(CONTENT_LENGTH);
+        }
+        if location.host_str() != url.host_str() {
+            for name in [AUTHORIZATION, COOKIE, PROXY_AUTHORIZATION] {
+                headers.remove(name);
             }
+        }
+        redirects.push(Redirect {
+            from: url,
+            to: location.clone(),
+            status,
         });
+        url = location;
     }
 }
 
 async fn _worker<Ctx, Out, Handler>(
-    url: Url,
-    data: Ctx,
-    handler: Arc<Handler>,
-    client: reqwest::Client,
-) -> NextActionVector<Ctx, Out>
+    next: &NextUrl<Ctx>,
+    shared: &Shared<Ctx, Handler>,
+) -> Result<NextActionVector<Ctx, Out>, Failure>
 where
     Ctx: Clone,
     Handler: HandlerWrapper<Ctx, Out>,
 {
-    let Ok(resp) = client.execute(Request::new(Method::GET, url.clone())).await else {
-        return Vec::new();
-    }; // TODO: Error Handling/Expo. Backoff
+    let attempt = next.attempt + 1;
+    let started = Instant::now();
+    let (resp, redirects) = _send(next, shared).await.map_err(|err| {
+        shared.observe(&next.url, None, Outcome::Error);
+        Failure::from_reqwest(&err)
+    })?;
+    Stats::incr(&shared.stats.fetched);
 
-    if resp.status() != 200 {
-        warn!("{} responsed with {}", url, resp.status());
+    let status = resp.status();
+    let outcome = if status.is_success() {
+        Outcome::Success
+    } else if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+        Outcome::Error
+    } else {
+        Outcome::Neutral
+    };
+    shared.observe(&next.url, Some(started.elapsed()), outcome);
+    if status != 200 {
+        warn!("{} responsed with {}", next.url, status);
+        if status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::SERVICE_UNAVAILABLE {
+            if let Some(delay) = resp.headers().get(RETRY_AFTER).and_then(parse_retry_after) {
+                // The host asked to back off, whether or not this url is retried.
+                let delay = shared.config.retry.retry_after(delay);
+                warn!(
+                    "{} responded with {}, pausing host for {:?}",
+                    next.url, status, delay
+                );
+                shared.frontier.pause_host(&next.url, delay);
+                Stats::incr(&shared.stats.host_paused);
+                let failure = Failure::RetryAfter(status, delay);
+                if shared.config.retry.will_retry(&failure, attempt) {
+                    return Err(failure);
+                }
+            }
+        }
+        let failure = Failure::Status(status);
+        if shared.config.retry.will_retry(&failure, attempt) {
+            return Err(failure);
+        }
     }
 
-    let Ok(resp) = Response::from_reqwest(resp).await else {
-        return Vec::new();
-    }; // TODO: Same as above
+    let mut resp = Response::from_reqwest(resp)
+        .await
+        .map_err(|err| match err {
+            Error::ReqwestError(err) => Failure::from_reqwest(&err),
+            _ => Failure::Other,
+        })?;
+    resp.original_url = next.url.clone();
+    resp.redirects = redirects;
+    resp.depth = next.depth;
+    resp.state = shared.config.app_state.clone();
+    resp.callback = match &next.callback {
+        Some(name) if !shared.config.callbacks.contains(name) => {
+            warn!(
+                "no handler named {:?} for {}, using the default ones",
+                name, next.url
+            );
+            None
+        }
+        callback => callback.clone(),
+    };
+    Stats::add(&shared.stats.bytes, resp.bytes.len() as u64);
+    shared.limits.add_bytes(resp.bytes.len() as u64);
 
     let resp = Arc::new(resp);
 
-    handler.handle(resp.clone(), data.clone()).await
+    let (mut actions, rejections) = shared
+        .handlers
+        .handle(resp.clone(), next.data.clone())
+        .await;
+    for rejection in rejections {
+        shared.reject(rejection);
+    }
+    if !status.is_success() {
+        actions.extend(
+            shared
+                .handlers
+                .handle_error(FetchError::Status(resp), next.data.clone())
+                .await,
+        );
+    }
+    Ok(actions)
 }
 
-async fn _fetcher<Ctx, Out, Handler>(
-    parallel_limit: usize,
-    cx: mpsc::Sender<NextUrl<Ctx>>,
-    mut rx: mpsc::Receiver<NextUrl<Ctx>>,
-    handlers: Arc<Handler>,
-    output_sender: mpsc::Sender<Out>,
-    duplicate: Arc<scc::HashSet<String>>,
-) where
+/// Retries or requeues `next`, handing it back if it is given up on.
+fn _handle_failure<Ctx, Handler>(
+    next: NextUrl<Ctx>,
+    failure: Failure,
+    shared: &Shared<Ctx, Handler>,
+) -> Option<NextUrl<Ctx>>
+where
+    Ctx: Clone + Send + Sync + 'static,
+{
+    let attempt = next.attempt + 1;
+    if !shared.config.retry.will_retry(&failure, attempt) {
+        error!(
+            "{} failed with {:?} after {} attempt(s), giving up",
+            next.url, failure, attempt
+        );
+        Stats::incr(&shared.stats.failed);
+        return Some(next);
+    }
+    if let Failure::RetryAfter(status, _) = failure {
+        // The host is already paused, so it is requeued straight away.
+        warn!(
+            "{} responded with {}, requeueing (attempt {})",
+            next.url, status, attempt
+        );
+        Stats::incr(&shared.stats.requeued);
+        shared.frontier.push(NextUrl { attempt, ..next });
+        return None;
+    }
+    let delay = shared.config.retry.backoff(attempt);
+    warn!(
+        "{} failed with {:?}, retrying in {:?} (attempt {})",
+        next.url, failure, delay, attempt
+    );
+    Stats::incr(&shared.stats.retried);
+    shared
+        .frontier
+        .push_after(NextUrl { attempt, ..next }, delay);
+    None
+}
+
+async fn _fetcher<Ctx, Out, Handler>(shared: Arc<Shared<Ctx, Handler>>, launch: Launch<Out>)
+where
     Handler: HandlerWrapper<Ctx, Out> + Send + Sync + 'static,
     Ctx: Clone + Debug + Send + Sync + 'static,
     Out: Debug + Send + 'static,
 {
-    let sem = Arc::new(Semaphore::new(parallel_limit));
-    let client = reqwest::Client::builder().build().unwrap();
-    while let Some(next) = rx.recv().await {
-        let handlers = handlers.clone();
+    let Launch {
+        output_sender,
+        handle: _handle,
+        mut commands,
+        phase,
+    } = launch;
+    let sem = Arc::new(Semaphore::new(shared.config.parallel_limit));
+    let mut tasks = JoinSet::new();
+    let period = shared.config.checkpoint_interval;
+    let mut checkpoints = tokio::time::interval_at((Instant::now() + period).into(), period);
+    let reason = loop {
+        let command = *commands.borrow_and_update();
+        match command {
+            CrawlState::Running => phase.send_replace(Phase::Running),
+            CrawlState::Paused => {
+                phase.send_replace(Phase::Paused);
+                tokio::select! {
+                    _ = commands.changed() => {}
+                    Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+                }
+                continue;
+            }
+            CrawlState::Draining => break StopReason::Drained,
+            CrawlState::Stopped => {
+                // No checkpoint: the last one still has everything in flight.
+                tasks.abort_all();
+                break StopReason::Stopped;
+            }
+        };
+        if let Some(reason) = shared.limits.reason() {
+            info!(
+                "budget exhausted ({:?}), finishing in-flight requests",
+                reason
+            );
+            break reason;
+        }
+        let next = async {
+            let permit = sem.clone().acquire_owned().await.unwrap();
+            shared.frontier.pop().await.map(|popped| (permit, popped))
+        };
+        let (permit, (next, slot, work)) = tokio::select! {
+            biased;
+            _ = commands.changed() => continue,
+            _ = shared.limits.exceeded() => continue,
+            _ = checkpoints.tick(), if shared.state.is_some() => {
+                shared.checkpoint().await;
+                continue;
+            }
+            Some(_) = tasks.join_next(), if !tasks.is_empty() => continue,
+            next = next => match next {
+                Some(next) => next,
+                None => break StopReason::Completed,
+            },
+        };
         let output_sender = output_sender.clone();
-        let cx = cx.clone();
-        let permit = sem.clone().acquire_owned().await.unwrap();
-        let duplicate = duplicate.clone();
-        let client = client.clone();
-        tokio::spawn(async move {
-            let url = Arc::new(next.url.host().map(|x| x.to_string()));
-            let actions = _worker(next.url, next.data, handlers, client).await;
+        let shared = shared.clone();
+        tasks.spawn(async move {
+            let _work = work;
+            if !shared.robots_allow(&next.url).await {
+                info!("{} is disallowed by robots.txt, skipping", next.url);
+                Stats::incr(&shared.stats.robots_denied);
+                slot.skip();
+                return;
+            }
+            // Taken after the robots check so denied urls are not counted.
+            if !shared.limits.take_page() {
+                // Popped before the budget ran out; put it back so the final
+                // checkpoint still has it.
+                shared.frontier.push(next);
+                return;
+            }
+            let depth = next.depth + 1;
+            let external_hops = next.external_hops;
+            let result = _worker(&next, &shared).await;
+            drop(slot);
             drop(permit);
-            let futs = actions.into_iter().map(move |next_action| {
-                let output_sender = output_sender.clone();
-                let duplicate = duplicate.clone();
-                let cx = cx.clone();
-                let url = url.clone();
-                async move {
-                    match next_action {
-                        NextAction::PipeOutput(output) => {
-                            output_sender
-                                .send(output)
-                                .await
-                                .unwrap_or_else(|err| error!("output_sender send error: {}", err));
+            let actions = match result {
+                Ok(actions) => actions,
+                Err(failure) => match _handle_failure(next, failure, &shared) {
+                    Some(next) => {
+                        let error = FetchError::Failure(next.url, failure);
+                        shared.handlers.handle_error(error, next.data).await
+                    }
+                    None => return,
+                },
+            };
+            for next_action in actions {
+                match next_action {
+                    NextAction::PipeOutput(output) => {
+                        if !shared.limits.take_output() {
+                            debug!("output budget exhausted, dropping {:?}", output);
+                            continue;
+                        }
+                        Stats::incr(&shared.stats.outputs);
+                        output_sender
+                            .send(output)
+                            .await
+                            .unwrap_or_else(|err| error!("output_sender send error: {}", err));
+                    }
+                    NextAction::Visit(mut pair) => {
+                        if shared.config.max_depth.is_some_and(|max| depth > max) {
+                            debug!("{} is beyond max depth, skipping", pair.url);
+                            Stats::incr(&shared.stats.too_deep);
+                            continue;
                         }
-                        NextAction::Visit(pair) => {
-                            if !pair.url.host().map(|x| x.to_string()).eq(&url) {
-                                return;
-                            }
-                            if duplicate
-                                .insert_async(pair.url.path().to_string())
-                                .await
-                                .is_err()
-                            {
-                                return;
-                            };
-                            cx.send(pair)
-                                .await
-                                .unwrap_or_else(|err| error!("next url send error: {}", err));
+                        pair.depth = depth;
+                        let Some(hops) = shared.scope.check(&pair.url, external_hops) else {
+                            debug!("{} is out of scope, skipping", pair.url);
+                            Stats::incr(&shared.stats.out_of_scope);
+                            continue;
+                        };
+                        pair.external_hops = hops;
+                        let _guard = shared.checkpoint_lock.read().await;
+                        if !shared.config.dedup.insert(shared.fingerprint(&pair)) {
+                            continue;
                         }
-                        NextAction::None => {}
+                        // Checked here when possible so that denied urls do
+                        // not take a turn from their host.
+                        if shared.robots_deny(&pair.url) {
+                            info!("{} is disallowed by robots.txt, skipping", pair.url);
+                            Stats::incr(&shared.stats.robots_denied);
+                            continue;
+                        }
+                        shared.frontier.push(pair);
                     }
+                    NextAction::None => {}
                 }
-            });
-            tokio::spawn(async move { join_all(futs).await });
+            }
         });
+    };
+    while tasks.join_next().await.is_some() {}
+    if reason != StopReason::Stopped {
+        shared.checkpoint().await;
+    }
+    *shared.stats.stop_reason.lock().unwrap() = Some(reason);
+    drop(output_sender);
+    phase.send_replace(Phase::Finished);
+    info!(
+        "finished crawling ({:?}), {:?}",
+        reason,
+        shared.stats.snapshot()
+    );
+}
+
+#[cfg(all(test, feature = "extractor"))]
+mod test {
+    use std::collections::BTreeSet;
+
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::{
+        client::Client,
+        extractor::{self, Depth, Redirects},
+        test_server::{follow, Out, Reply, TestServer},
+    };
+
+    async fn crawl<W: WebsiteWrapper<Out> + 'static>(website: W) -> Vec<String> {
+        let stream = Client::handle(website).stream();
+        tokio::time::timeout(Duration::from_secs(10), stream.collect::<Vec<_>>())
+            .await
+            .expect("the crawl did not finish")
+            .into_iter()
+            .map(|Out(path)| path)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_crawl_completes() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links(["/a", "/b"]),
+            "/a" => Reply::links(["/", "/b"]),
+            _ => Reply::links(["/a"]),
+        })
+        .await;
+        let website = Website::handle(follow)
+            .start_with(server.url("/"))
+            .respect_robots(false);
+
+        let mut paths = crawl(Website::from(website)).await;
+        paths.sort();
+        assert_eq!(paths, ["/", "/a", "/b"]);
+    }
+
+    #[tokio::test]
+    async fn test_crawl_completes_after_retries() {
+        let flaky = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let busy = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let server = TestServer::start(move |request| {
+            use std::sync::atomic::Ordering::Relaxed;
+            match request.path.as_str() {
+                "/" => Reply::links(["/flaky", "/busy"]),
+                "/flaky" if flaky.fetch_add(1, Relaxed) < 2 => Reply::status(500),
+                "/busy" if busy.fetch_add(1, Relaxed) < 1 => {
+                    Reply::status(503).header("retry-after", "1")
+                }
+                _ => Reply::html("leaf"),
+            }
+        })
+        .await;
+        let website = Website::from(
+            Website::handle(follow)
+                .start_with(server.url("/"))
+                .respect_robots(false)
+                .retry(RetryPolicy::default().base_delay(Duration::from_millis(10))),
+        );
+        let stats = website.stats();
+
+        let mut paths = crawl(website).await;
+        paths.sort();
+        assert_eq!(paths, ["/", "/busy", "/flaky"]);
+        let stats = stats.snapshot();
+        assert_eq!((stats.retried, stats.requeued), (2, 1));
+        assert_eq!(stats.stop_reason, Some(StopReason::Completed));
+    }
+
+    #[tokio::test]
+    async fn test_max_depth() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links(["/1"]),
+            "/1" => Reply::links(["/2"]),
+            _ => Reply::links(["/3"]),
+        })
+        .await;
+        let website = Website::from(
+            Website::handle(follow)
+                .and(
+                    |Depth(depth): Depth, extractor::Url(url): extractor::Url| async move {
+                        vec![NextAction::<(), _>::PipeOutput(Out(format!(
+                            "{} at {}",
+                            url.path(),
+                            depth
+                        )))]
+                    },
+                )
+                .start_with(server.url("/"))
+                .respect_robots(false)
+                .max_depth(1),
+        );
+        let stats = website.stats();
+
+        let mut paths = crawl(website).await;
+        paths.sort();
+        assert_eq!(paths, ["/", "/ at 0", "/1", "/1 at 1"]);
+        assert_eq!(stats.snapshot().too_deep, 1);
+    }
+
+    /// Emits every redirect hop, then the final url.
+    async fn hops(
+        Redirects(redirects): Redirects,
+        extractor::Url(url): extractor::Url,
+    ) -> Vec<NextAction<(), Out>> {
+        redirects
+            .iter()
+            .map(|hop| {
+                format!(
+                    "{} -> {} {}",
+                    hop.from.path(),
+                    hop.to.path(),
+                    hop.status.as_u16()
+                )
+            })
+            .chain([format!("at {}", url)])
+            .map(|hop| NextAction::PipeOutput(Out(hop)))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_redirect_chain() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/a" => Reply::redirect(301, "/b"),
+            "/b" => Reply::redirect(302, "/c"),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        let website = Website::handle(hops)
+            .start_with(server.url("/a"))
+            .respect_robots(false);
+
+        assert_eq!(
+            crawl(Website::from(website)).await,
+            [
+                "/a -> /b 301".to_string(),
+                "/b -> /c 302".to_string(),
+                format!("at {}", server.url("/c")),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redirect_strips_credentials_across_hosts() {
+        let home = Arc::new(std::sync::OnceLock::<Url>::new());
+        let other = home.clone();
+        let server = TestServer::start(move |request| match request.path.as_str() {
+            "/login" => Reply::redirect(302, "/same"),
+            "/same" => Reply::redirect(302, other.get().unwrap().as_str()),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        home.set(server.url_on("localhost", "/home")).unwrap();
+        let login = NextUrl::new(server.url("/login"), ())
+            .header(AUTHORIZATION, "Bearer secret".parse().unwrap())
+            .header(COOKIE, "session=1".parse().unwrap());
+        let website = Website::handle(move |extractor::Url(url): extractor::Url| {
+            let login = login.clone();
+            async move {
+                match url.path() {
+                    "/" => vec![NextAction::<(), Out>::Visit(login)],
+                    _ => vec![NextAction::PipeOutput(Out(url.to_string()))],
+                }
+            }
+        })
+        .start_with(server.url("/"))
+        .respect_robots(false);
+
+        assert_eq!(
+            crawl(Website::from(website)).await,
+            [home.get().unwrap().to_string()]
+        );
+        let requests = server.requests();
+        let credentials = |path: &str| {
+            let request = requests
+                .iter()
+                .find(|request| request.path == path)
+                .unwrap();
+            ["authorization", "cookie"].map(|name| request.headers.contains_key(name))
+        };
+        assert_eq!(credentials("/login"), [true, true]);
+        assert_eq!(credentials("/same"), [true, true]);
+        assert_eq!(credentials("/home"), [false, false]);
+    }
+
+    #[tokio::test]
+    async fn test_redirect_method() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/see-other" => Reply::redirect(303, "/got"),
+            "/found" => Reply::redirect(302, "/got"),
+            "/temporary" => Reply::redirect(307, "/posted"),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        let post = |path: &str| {
+            NextUrl::new(server.url(path), ())
+                .method(Method::POST)
+                .body("q=1")
+                .header(CONTENT_TYPE, "text/plain".parse().unwrap())
+        };
+        let seeds = vec![post("/see-other"), post("/found"), post("/temporary")];
+        let website = Website::handle(move |extractor::Url(url): extractor::Url| {
+            let seeds = seeds.clone();
+            async move {
+                match url.path() {
+                    "/" => seeds.into_iter().map(NextAction::Visit).collect(),
+                    _ => Vec::<NextAction<(), Out>>::new(),
+                }
+            }
+        })
+        .start_with(server.url("/"))
+        .respect_robots(false);
+
+        crawl(Website::from(website)).await;
+        let mut requests: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|request| request.path != "/")
+            .map(|request| {
+                let content_type = request.headers.contains_key("content-type");
+                (request.method, request.path, content_type)
+            })
+            .collect();
+        requests.sort();
+        let expect = |method: &str, path: &str, content_type| {
+            (method.to_string(), path.to_string(), content_type)
+        };
+        assert_eq!(
+            requests,
+            [
+                expect("GET", "/got", false),
+                expect("GET", "/got", false),
+                expect("POST", "/found", true),
+                expect("POST", "/posted", true),
+                expect("POST", "/see-other", true),
+                expect("POST", "/temporary", true),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redirect_limit() {
+        let server = TestServer::start(|request| {
+            let n: usize = request.path.trim_start_matches("/loop/").parse().unwrap();
+            Reply::redirect(302, format!("/loop/{}", n + 1))
+        })
+        .await;
+        let website = Website::handle(follow)
+            .start_with(server.url("/loop/0"))
+            .respect_robots(false)
+            .on_error(|err, _| async move {
+                match err {
+                    FetchError::Status(resp) => vec![NextAction::<(), Out>::PipeOutput(Out(
+                        format!("{} after {}", resp.status.as_u16(), resp.redirects.len()),
+                    ))],
+                    FetchError::Failure(..) => Vec::new(),
+                }
+            });
+
+        assert_eq!(crawl(Website::from(website)).await, ["302 after 10"]);
+        assert_eq!(server.requests().len(), MAX_REDIRECTS + 1);
+    }
+
+    #[tokio::test]
+    async fn test_named_handlers() {
+        let server = TestServer::start(|_| Reply::html("page")).await;
+        let website = Website::handle(|extractor::Url(url): extractor::Url| async move {
+            let mut actions = vec![NextAction::<(), Out>::PipeOutput(Out(format!(
+                "default {}",
+                url.path()
+            )))];
+            if url.path() == "/" {
+                for (path, name) in [("/a", "detail"), ("/b", "missing")] {
+                    let next = NextUrl::new(url.join(path).unwrap(), ()).callback(name);
+                    actions.push(NextAction::Visit(next));
+                }
+            }
+            actions
+        })
+        .named("detail", |extractor::Url(url): extractor::Url| async move {
+            vec![NextAction::<(), Out>::PipeOutput(Out(format!(
+                "detail {}",
+                url.path()
+            )))]
+        })
+        .start_with(server.url("/"))
+        .respect_robots(false);
+
+        let mut outputs = crawl(Website::from(website)).await;
+        outputs.sort();
+        assert_eq!(outputs, ["default /", "default /b", "detail /a"]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_resume_after_max_pages() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links((1..=10).map(|n| format!("/{}", n))),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        let dir = std::env::temp_dir().join(format!("syphon-budget-{}", uuid::Uuid::new_v4()));
+        let website = || {
+            Website::handle(follow)
+                .start_with(server.url("/"))
+                .respect_robots(false)
+                .persist(&dir)
+        };
+
+        let first = crawl(Website::from(website().max_pages(3))).await;
+        assert_eq!(first.len(), 3);
+        let second = crawl(Website::from(website())).await;
+        assert_eq!(second.len(), 8);
+
+        let seen: BTreeSet<_> = first.iter().chain(&second).collect();
+        assert_eq!(seen.len(), 11);
+        assert_eq!(server.requests().len(), 11);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_robots_denied_urls_skip_crawl_delay() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/robots.txt" => Reply::html("User-agent: *\nDisallow: /private\nCrawl-delay: 1\n"),
+            "/" => Reply::links([
+                "/private/1",
+                "/private/2",
+                "/private/3",
+                "/private/4",
+                "/public",
+            ]),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        let website = Website::from(Website::handle(follow).start_with(server.url("/")));
+        let stats = website.stats();
+
+        let started = Instant::now();
+        assert_eq!(crawl(website).await.len(), 2);
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert_eq!(stats.snapshot().robots_denied, 4);
+        assert!(server
+            .requests()
+            .iter()
+            .all(|request| !request.path.starts_with("/private")));
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_pauses_host_without_retry() {
+        let server = TestServer::start(|request| match request.path.as_str() {
+            "/" => Reply::links(["/busy", "/after"]),
+            "/busy" => Reply::status(429).header("retry-after", "2"),
+            _ => Reply::html("leaf"),
+        })
+        .await;
+        let website = Website::from(
+            Website::handle(follow)
+                .start_with(server.url("/"))
+                .respect_robots(false)
+                .host_parallel_limit(1)
+                .retry(RetryPolicy::none()),
+        );
+        let stats = website.stats();
+
+        let started = Instant::now();
+        assert_eq!(crawl(website).await, ["/", "/after"]);
+        assert!(started.elapsed() >= Duration::from_secs(2));
+        let stats = stats.snapshot();
+        assert_eq!((stats.host_paused, stats.requeued), (1, 0));
     }
 }
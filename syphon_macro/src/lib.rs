@@ -115,13 +115,25 @@ impl ToTokens for Field<'_> {
 }
 
 impl<'src> Field<'src> {
+    /// The selector is parsed once per type into a static, and an invalid
+    /// one panics on first use with the field name.
     fn output(&self) -> proc_macro2::TokenStream {
         let name = self.name;
         let field = self.field_type.output();
         let varience = self.varience.output();
         let selector: &str = &self.selector.replace('\"', "");
+        let field_name = name.to_string();
         quote!(
-            let #name = dom.select(&scraper::Selector::parse(#selector).ok()?)
+            let #name = {
+                static SELECTOR: ::std::sync::OnceLock<scraper::Selector> =
+                    ::std::sync::OnceLock::new();
+                SELECTOR.get_or_init(|| {
+                    scraper::Selector::parse(#selector).unwrap_or_else(|err| {
+                        panic!("invalid selector {:?} on field `{}`: {}", #selector, #field_name, err)
+                    })
+                })
+            };
+            let #name = dom.select(#name)
                 #varience
                 #field
         )